@@ -0,0 +1,189 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Error, Result};
+
+use crate::identity::SeatId;
+
+pub const MAX_TEXT_LENGTH: usize = 200;
+pub const HISTORY_LENGTH: usize = 50;
+
+// every seat may post a handful of messages in a sliding window
+const RATE_LIMIT: usize = 5;
+const RATE_WINDOW: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(u8)]
+pub enum Emote {
+    Hello = 1,
+    GoodLuck = 2,
+    GoodGame = 3,
+    WellPlayed = 4,
+    Oops = 5,
+    Thanks = 6,
+}
+
+impl TryFrom<u8> for Emote {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        return match value {
+            1 => Ok(Emote::Hello),
+            2 => Ok(Emote::GoodLuck),
+            3 => Ok(Emote::GoodGame),
+            4 => Ok(Emote::WellPlayed),
+            5 => Ok(Emote::Oops),
+            6 => Ok(Emote::Thanks),
+            _ => Err(anyhow!("unknown emote")),
+        };
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChatContent {
+    Text(String),
+    Emote(Emote),
+}
+
+impl ChatContent {
+    pub fn parse_text(bin: &[u8]) -> Result<Self> {
+        let text = String::from_utf8(bin.to_vec())?.trim().to_string();
+
+        if text.is_empty() || text.chars().count() > MAX_TEXT_LENGTH {
+            return Err(anyhow!(
+                "message must be 1 to {} characters long",
+                MAX_TEXT_LENGTH
+            ));
+        }
+
+        if text.chars().any(|c| c.is_control()) {
+            return Err(anyhow!("message cant contain control characters"));
+        }
+
+        return Ok(ChatContent::Text(text));
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ChatMessage {
+    pub message_id: u32,
+    pub seat: SeatId,
+    pub content: ChatContent,
+}
+
+// a room keeps the latest messages around, so that returning players can catch up
+pub struct Chat {
+    history: VecDeque<ChatMessage>,
+    message_count: u32,
+    recent: VecDeque<(SeatId, Instant)>,
+}
+
+impl Chat {
+    pub fn new() -> Self {
+        return Chat {
+            history: VecDeque::new(),
+            message_count: 0,
+            recent: VecDeque::new(),
+        };
+    }
+
+    pub fn post(&mut self, seat: SeatId, content: ChatContent) -> Result<()> {
+        let now = Instant::now();
+        while self
+            .recent
+            .front()
+            .is_some_and(|(_, posted_at)| now.duration_since(*posted_at) > RATE_WINDOW)
+        {
+            self.recent.pop_front();
+        }
+
+        let posted = self
+            .recent
+            .iter()
+            .filter(|(other, _)| *other == seat)
+            .count();
+        if posted >= RATE_LIMIT {
+            return Err(anyhow!("cant post, too many messages"));
+        }
+
+        self.recent.push_back((seat, now));
+        self.message_count += 1;
+        self.history.push_back(ChatMessage {
+            message_id: self.message_count,
+            seat,
+            content,
+        });
+
+        if self.history.len() > HISTORY_LENGTH {
+            self.history.pop_front();
+        }
+
+        return Ok(());
+    }
+
+    // messages after the last one a connection has seen, the whole history for a new one
+    pub fn get_since(&self, message_id: u32) -> Vec<ChatMessage> {
+        return self
+            .history
+            .iter()
+            .filter(|message| message.message_id > message_id)
+            .cloned()
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod chat_test {
+    use anyhow::Result;
+
+    use super::{Chat, ChatContent, Emote, HISTORY_LENGTH, RATE_LIMIT};
+    use crate::identity::SeatId;
+
+    #[test]
+    pub fn test_parse_text() {
+        let content = ChatContent::parse_text(" ahoy ".as_bytes()).unwrap();
+        assert_eq!(content, ChatContent::Text(String::from("ahoy")));
+
+        assert!(ChatContent::parse_text(" ".as_bytes()).is_err());
+        assert!(ChatContent::parse_text("a".repeat(201).as_bytes()).is_err());
+        assert!(ChatContent::parse_text("a\u{7}b".as_bytes()).is_err());
+    }
+
+    #[test]
+    pub fn test_post() -> Result<()> {
+        let mut chat = Chat::new();
+
+        for _ in 0..RATE_LIMIT {
+            chat.post(SeatId(0), ChatContent::Emote(Emote::Hello))?;
+        }
+
+        assert!(chat
+            .post(SeatId(0), ChatContent::Emote(Emote::Oops))
+            .is_err());
+        chat.post(SeatId(1), ChatContent::Emote(Emote::GoodLuck))?;
+
+        let messages = chat.get_since(RATE_LIMIT as u32);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].seat, SeatId(1));
+        assert_eq!(chat.get_since(0).len(), RATE_LIMIT + 1);
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_history_length() -> Result<()> {
+        let mut chat = Chat::new();
+
+        for seat in 0..HISTORY_LENGTH as u8 + 1 {
+            chat.post(SeatId(seat), ChatContent::Emote(Emote::Thanks))?;
+        }
+
+        let messages = chat.get_since(0);
+        assert_eq!(messages.len(), HISTORY_LENGTH);
+        assert_eq!(messages[0].message_id, 2);
+
+        return Ok(());
+    }
+}
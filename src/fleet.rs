@@ -0,0 +1,177 @@
+use anyhow::{anyhow, Result};
+
+use crate::{
+    game_rules::GameRules,
+    player::Player,
+    playmap::Playmap,
+    point::{Orientation, Point},
+    random::Random,
+    ship::Ship,
+};
+
+// a search that gets stuck on a crowded board starts over from an empty one, a few times
+// before giving up
+const ATTEMPTS: usize = 20;
+const PLACEMENT_BUDGET: usize = 500;
+
+// seed of the layout that proves a fleet fits, so the check gives the same answer every time
+const FIT_SEED: u64 = 1;
+
+// lays out the whole fleet at random, going back on earlier ships when the later ones
+// don't find room, biggest ships first
+pub fn place_fleet(rules: &GameRules, random: &mut Random) -> Result<Player> {
+    let sizes: Vec<u8> = (1..=4)
+        .rev()
+        .flat_map(|size| (0..rules.get_limit(size)).map(move |_| size))
+        .collect();
+
+    for _ in 0..ATTEMPTS {
+        let mut player = Player::with_size(rules.board_size);
+        let mut budget = PLACEMENT_BUDGET;
+
+        if place_ships(&mut player, rules, &sizes, random, &mut budget)? {
+            return Ok(player);
+        }
+    }
+
+    return Err(anyhow!("fleet does not fit on the board"));
+}
+
+// a fleet that fits can always be laid out again, if a random search runs out of luck
+pub fn place_fitting_fleet(rules: &GameRules, random: &mut Random) -> Result<Player> {
+    return place_fleet(rules, random).or_else(|_| place_fleet(rules, &mut Random::new(FIT_SEED)));
+}
+
+pub fn check_fit(rules: &GameRules) -> Result<()> {
+    place_fleet(rules, &mut Random::new(FIT_SEED))?;
+    return Ok(());
+}
+
+fn place_ships(
+    player: &mut Player,
+    rules: &GameRules,
+    sizes: &[u8],
+    random: &mut Random,
+    budget: &mut usize,
+) -> Result<bool> {
+    let Some((size, rest)) = sizes.split_first() else {
+        return Ok(true);
+    };
+
+    let mut candidates = get_candidates(player, *size)?;
+    for index in (1..candidates.len()).rev() {
+        candidates.swap(index, random.next_below(index + 1));
+    }
+
+    for (ship, point) in candidates {
+        if *budget == 0 {
+            return Ok(false);
+        }
+        *budget -= 1;
+
+        player.place_figure(ship, point, rules.get_armor(ship))?;
+        if place_ships(player, rules, rest, random, budget)? {
+            return Ok(true);
+        }
+        player.remove_figure(point)?;
+    }
+
+    return Ok(false);
+}
+
+fn get_candidates(player: &Player, size: u8) -> Result<Vec<(Ship, Point)>> {
+    let board_size = player.board_size;
+    let orientations = match size {
+        1 => vec![Orientation::Horizontal],
+        _ => vec![Orientation::Horizontal, Orientation::Vertical],
+    };
+
+    // the cells taken by or touching a ship are worked out once for all candidates, and
+    // without going through errors at the edges, which a search this long can't afford
+    let mut blocked = Playmap::with_size(board_size);
+    for point in Point::all(board_size) {
+        if player.has_ship_at(point) {
+            blocked.mark_field(point);
+            blocked.mark_field(Point {
+                x: point.x + 1,
+                ..point
+            });
+            blocked.mark_field(Point {
+                y: point.y + 1,
+                ..point
+            });
+            blocked.mark_field(Point {
+                x: point.x.wrapping_sub(1),
+                ..point
+            });
+            blocked.mark_field(Point {
+                y: point.y.wrapping_sub(1),
+                ..point
+            });
+        }
+    }
+
+    let mut candidates = Vec::new();
+    for orientation in orientations {
+        let ship = Ship::from_size(size, orientation)?;
+
+        for point in Point::all(board_size) {
+            let cells: Vec<Point> = (0..size)
+                .map(|offset| match orientation {
+                    Orientation::Horizontal => Point {
+                        x: point.x + offset,
+                        ..point
+                    },
+                    Orientation::Vertical => Point {
+                        y: point.y + offset,
+                        ..point
+                    },
+                })
+                .collect();
+
+            let is_free = cells
+                .iter()
+                .all(|cell| cell.is_on_board(board_size) && !blocked.is_marked_field(*cell));
+            if is_free {
+                candidates.push((ship, point));
+            }
+        }
+    }
+
+    return Ok(candidates);
+}
+
+#[cfg(test)]
+mod fleet_test {
+    use anyhow::Result;
+
+    use super::{check_fit, place_fleet};
+    use crate::{game_rules::GameRules, preset::Preset, random::Random};
+
+    #[test]
+    pub fn test_place_fleet() -> Result<()> {
+        let rules = Preset::CoOp.get_rules().get_armada_rules();
+
+        for seed in 0..20 {
+            let player = place_fleet(&rules, &mut Random::new(seed))?;
+
+            assert_eq!(player.submarines, 8);
+            assert_eq!(player.battleships, 2);
+            assert_eq!(player.ships.count(), 40);
+        }
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_check_fit() {
+        let rules = GameRules::new();
+        assert!(check_fit(&rules).is_ok());
+
+        let crowded = GameRules {
+            submarine_limit: 30,
+            ..rules
+        };
+        assert!(check_fit(&crowded).is_err());
+    }
+}
@@ -1,41 +1,152 @@
-use std::sync::mpsc::{channel, Receiver, Sender};
+use std::{
+    sync::mpsc::{channel, Receiver, Sender},
+    time::{Duration, Instant},
+};
 
 use anyhow::{anyhow, Result};
 
 use crate::{
-    game_rules::GameRules, game_stage::GameStage, player::Player, playmap::Playmap, point::Point,
+    chat::{Chat, ChatContent, ChatMessage},
+    fleet,
+    game_rules::{FirstPlayer, GameRules, WinCondition},
+    game_stage::GameStage,
+    handicap::Handicap,
+    identity::{ConnectionId, SeatId},
+    nickname::Nickname,
+    offer::Offer,
+    player::Player,
+    playmap::Playmap,
+    point::{Direction, Point},
+    random::{self, Random},
+    series::Series,
     ship::Ship,
 };
 
-type PlayerId = u8;
-
 pub struct Game {
     pub rules: GameRules,
     pub stage: GameStage,
 
-    pub player_a: Player,
-    pub player_b: Player,
+    seats: Vec<Seat>,
+    spectators: Vec<Spectator>,
+    team_turns: Vec<usize>,
 
-    connection_count: PlayerId,
-    connection_a: Option<PlayerId>,
-    connection_b: Option<PlayerId>,
+    pending_shots: Vec<(SeatId, usize, Point)>,
+    round: u8,
+    turn_shots: u8,
 
-    sender_a: Option<Sender<()>>,
-    sender_b: Option<Sender<()>>,
+    random: Random,
+    first_player: Option<SeatId>,
+    last_winner: Option<SeatId>,
+
+    offer: Option<(SeatId, Offer)>,
+    accepted: Vec<SeatId>,
+    series: Series,
+    paused_stage: Option<GameStage>,
+    chat: Chat,
 }
 
 pub struct StateSnapshot {
+    pub my_seat: usize,
+    pub turn_seat: Option<usize>,
     pub my_ships: Playmap,
     pub my_marks: Playmap,
-    pub enemy_marks: Playmap,
-    pub enemy_losses: Playmap,
+    pub my_damage: Playmap,
+    pub first_player: Option<SeatId>,
+    pub best_of: u8,
+    pub my_wins: u8,
+    pub round: u8,
+    pub offer: Option<(SeatId, Offer)>,
+    pub allies: Vec<AllyView>,
+    pub enemies: Vec<EnemyView>,
+}
+
+pub struct AllyView {
+    pub seat: usize,
+    pub nickname: Option<Nickname>,
+    pub is_alive: bool,
+    pub ships: Playmap,
+    pub marks: Playmap,
+    pub damage: Playmap,
+}
+
+pub struct EnemyView {
+    pub seat: usize,
+    pub nickname: Option<Nickname>,
+    pub is_alive: bool,
+    pub wins: u8,
+    pub marks: Playmap,
+    pub losses: Playmap,
+    pub damage: Playmap,
+    pub pings: Playmap,
+}
+
+// what spectators get to see: everyone's shots and hits, the fleets only when revealed
+pub struct PublicView {
+    pub stage: GameStage,
+    pub turn_seat: Option<usize>,
+    pub best_of: u8,
+    pub round: u8,
+    pub players: Vec<PlayerView>,
+}
+
+pub struct PlayerView {
+    pub seat: usize,
+    pub nickname: Option<Nickname>,
+    pub is_alive: bool,
+    pub wins: u8,
+    pub ships: Playmap,
+    pub marks: Playmap,
+    pub losses: Playmap,
+    pub damage: Playmap,
+}
+
+struct Spectator {
+    connection: ConnectionId,
+    sender: Sender<()>,
+}
+
+struct Seat {
+    team: usize,
+    is_computer: bool,
+    connection: Option<ConnectionId>,
+    nickname: Option<Nickname>,
+    token: Option<u64>,
+    sender: Option<Sender<()>>,
+    disconnected_at: Option<Instant>,
+    player: Player,
+    handicap: Handicap,
+    has_resigned: bool,
 }
 
-struct Players<'a> {
-    my_id: PlayerId,
-    me: &'a mut Player,
-    enemy_id: PlayerId,
-    enemy: &'a mut Player,
+impl Seat {
+    fn new(team: usize, board_size: u8) -> Self {
+        return Seat {
+            team,
+            is_computer: false,
+            connection: None,
+            nickname: None,
+            token: None,
+            sender: None,
+            disconnected_at: None,
+            player: Player::with_size(board_size),
+            handicap: Handicap::new(),
+            has_resigned: false,
+        };
+    }
+
+    fn new_computer(team: usize, board_size: u8) -> Self {
+        return Seat {
+            is_computer: true,
+            connection: Some(ConnectionId::COMPUTER),
+            ..Seat::new(team, board_size)
+        };
+    }
+
+    // nobody is sunk before placing their ships
+    fn is_alive(&self) -> bool {
+        let is_sunk = self.player.ships.count() != 0 && !self.player.has_intact_ships();
+        return !self.has_resigned && !is_sunk;
+    }
 }
 
 impl Game {
@@ -44,215 +155,2339 @@ impl Game {
             rules: rules,
             stage: GameStage::Waiting,
 
-            player_a: Player::new(),
-            player_b: Player::new(),
+            seats: (0..rules.get_seat_count())
+                .map(|seat| {
+                    let team = rules.get_team(seat);
+                    let board_size = rules.get_seat_rules(seat).board_size;
+
+                    return match seat < rules.player_count as usize {
+                        true => Seat::new(team, board_size),
+                        false => Seat::new_computer(team, board_size),
+                    };
+                })
+                .collect(),
+            spectators: Vec::new(),
+            team_turns: Game::get_initial_team_turns(&rules),
 
-            connection_count: 0,
-            connection_a: None,
-            connection_b: None,
+            pending_shots: Vec::new(),
+            round: 0,
+            turn_shots: 0,
 
-            sender_a: None,
-            sender_b: None,
+            random: match rules.first_player {
+                FirstPlayer::CoinFlip(seed) => Random::new(seed),
+                _ => Random::from_entropy(),
+            },
+            first_player: None,
+            last_winner: None,
+
+            offer: None,
+            accepted: Vec::new(),
+            series: Series::new(rules.series_length),
+            paused_stage: None,
+            chat: Chat::new(),
         };
     }
 
-    pub fn connect(&mut self) -> Result<(PlayerId, Receiver<()>)> {
-        if self.connection_a.is_some() && self.connection_b.is_some() {
-            return Err(anyhow!("both seats taken"));
+    pub fn connect(&mut self) -> Result<(SeatId, Receiver<()>)> {
+        return self.take_seat(ConnectionId::new());
+    }
+
+    fn take_seat(&mut self, connection_id: ConnectionId) -> Result<(SeatId, Receiver<()>)> {
+        // a finished game is only kept around until someone takes the place of a leaver
+        let has_leavers = self.seats.iter().any(|seat| seat.disconnected_at.is_some());
+        if self.stage.is_finished() && has_leavers {
+            self.release_seats();
         }
 
-        self.connection_count += 1;
+        let Some(my_seat) = self.seats.iter().position(|seat| seat.connection.is_none()) else {
+            return Err(anyhow!("all seats taken"));
+        };
+
+        let is_last_seat = self
+            .seats
+            .iter()
+            .filter(|seat| seat.connection.is_none())
+            .count()
+            == 1;
+        let armada = match is_last_seat {
+            true => self.plan_armada()?,
+            false => Vec::new(),
+        };
 
+        let token = u64::from_be_bytes(random::get_secure_bytes()?);
         let (sender, receiver) = channel();
 
-        if self.connection_a.is_none() {
-            self.connection_a = Some(self.connection_count);
-            self.sender_a = Some(sender);
-        } else {
-            self.connection_b = Some(self.connection_count);
-            self.sender_b = Some(sender);
-        }
+        let seat = &mut self.seats[my_seat];
+        seat.connection = Some(connection_id);
+        seat.token = Some(token);
+        seat.sender = Some(sender);
 
-        if self.connection_a.is_some() && self.connection_b.is_some() {
+        if is_last_seat {
             self.stage = GameStage::PlayerShips(None);
+            self.deploy_armada(armada);
         }
 
-        return Ok((self.connection_count, receiver));
+        return Ok((self.get_seat_id(my_seat)?, receiver));
     }
 
-    pub fn disconnect(&mut self, my_id: PlayerId) -> Result<()> {
-        if self.connection_a == Some(my_id) {
-            self.connection_a = self.connection_b.take();
-            self.sender_a = self.sender_b.take();
-        } else if self.connection_b == Some(my_id) {
-            self.connection_b = None;
-            self.sender_b = None;
-        } else {
-            return Err(anyhow!("player not found"));
+    // spectators take no seat, they only get notified whenever the game changes
+    pub fn spectate(&mut self, connection_id: ConnectionId) -> Result<Receiver<()>> {
+        if self.spectators.len() >= self.rules.spectator_limit as usize {
+            return Err(anyhow!("cant spectate, too many spectators"));
+        }
+
+        let (sender, receiver) = channel();
+        sender.send(())?;
+
+        self.spectators.push(Spectator {
+            connection: connection_id,
+            sender,
+        });
+
+        return Ok(receiver);
+    }
+
+    pub fn stop_spectating(&mut self, connection_id: ConnectionId) {
+        self.spectators
+            .retain(|spectator| spectator.connection != connection_id);
+    }
+
+    // players go by their nickname, so no two of them can share it in a room
+    pub fn connect_as(
+        &mut self,
+        connection_id: ConnectionId,
+        nickname: Nickname,
+    ) -> Result<(SeatId, Receiver<()>)> {
+        if self.is_nickname_taken(&nickname) {
+            return Err(anyhow!("cant join room, nickname {} is taken", nickname));
+        }
+
+        let (my_id, receiver) = self.take_seat(connection_id)?;
+        self.seats[my_id.get_index()].nickname = Some(nickname);
+
+        return Ok((my_id, receiver));
+    }
+
+    pub fn is_nickname_taken(&self, nickname: &Nickname) -> bool {
+        return self
+            .seats
+            .iter()
+            .any(|seat| seat.connection.is_some() && seat.nickname.as_ref() == Some(nickname));
+    }
+
+    pub fn reconnect(
+        &mut self,
+        connection_id: ConnectionId,
+        token: u64,
+    ) -> Result<(SeatId, Receiver<()>)> {
+        // nobody may be left to notice that the window has passed
+        self.expire_sessions()?;
+
+        let my_seat = self
+            .seats
+            .iter()
+            .position(|seat| seat.token == Some(token) && seat.disconnected_at.is_some())
+            .ok_or(anyhow!("cant reconnect, session not found"))?;
+
+        let window = Duration::from_secs(self.rules.reconnect_window as u64);
+        let seat = &mut self.seats[my_seat];
+        if seat.has_resigned {
+            return Err(anyhow!("cant reconnect, game was forfeited"));
+        }
+
+        if seat
+            .disconnected_at
+            .is_some_and(|at| at.elapsed() >= window)
+        {
+            return Err(anyhow!("cant reconnect, session expired"));
+        }
+
+        let (sender, receiver) = channel();
+
+        seat.connection = Some(connection_id);
+        seat.sender = Some(sender);
+        seat.disconnected_at = None;
+
+        return Ok((self.get_seat_id(my_seat)?, receiver));
+    }
+
+    // leaving on purpose forfeits a running game straight away
+    pub fn leave(&mut self, my_id: SeatId) -> Result<()> {
+        if self.stage.is_playing() || self.stage == GameStage::Paused {
+            self.forfeit(my_id, GameStage::PlayerAbandons(my_id))?;
+        }
+
+        return self.disconnect(my_id);
+    }
+
+    pub fn disconnect(&mut self, my_id: SeatId) -> Result<()> {
+        let my_seat = self.get_seat(my_id)?;
+
+        self.seats[my_seat].sender = None;
+
+        // once the game has started, the seat is kept so that the player can come back
+        // to a running game and the final state of a finished one stays on record
+        if self.stage != GameStage::Waiting {
+            self.seats[my_seat].disconnected_at = Some(Instant::now());
+            return Ok(());
+        }
+
+        self.release_seats();
+
+        return Ok(());
+    }
+
+    // everyone still here keeps their seat, while the seats of leavers are freed up
+    fn release_seats(&mut self) {
+        for seat in self.seats.iter_mut() {
+            if !seat.is_computer && seat.sender.is_none() {
+                *seat = Seat::new(seat.team, seat.player.board_size);
+            }
         }
 
-        self.player_a = Player::new();
-        self.player_b = Player::new();
+        self.reset_players();
+
+        self.first_player = None;
+        self.last_winner = None;
+        self.series.reset();
 
         self.stage = GameStage::Waiting;
+    }
+
+    // players who don't come back in time forfeit the game
+    pub fn expire_sessions(&mut self) -> Result<bool> {
+        let window = Duration::from_secs(self.rules.reconnect_window as u64);
+
+        if !self.stage.is_playing() && self.stage != GameStage::Paused {
+            return Ok(false);
+        }
+
+        let expired: Vec<SeatId> = (0..self.seats.len())
+            .filter(|seat| !self.seats[*seat].has_resigned)
+            .filter(|seat| {
+                self.seats[*seat]
+                    .disconnected_at
+                    .is_some_and(|at| at.elapsed() >= window)
+            })
+            .map(|seat| SeatId(seat as u8))
+            .collect();
+
+        for player_id in &expired {
+            if self.stage.is_playing() || self.stage == GameStage::Paused {
+                self.forfeit(*player_id, GameStage::PlayerAbandons(*player_id))?;
+            }
+        }
+
+        return Ok(!expired.is_empty());
+    }
+
+    pub fn get_session_token(&self, my_id: SeatId) -> Result<u64> {
+        return self.seats[self.get_seat(my_id)?]
+            .token
+            .ok_or(anyhow!("session not found"));
+    }
+
+    // chat stays open in every stage, also after the game is over
+    pub fn post_chat(&mut self, my_id: SeatId, content: ChatContent) -> Result<()> {
+        self.get_seat(my_id)?;
+        return self.chat.post(my_id, content);
+    }
+
+    pub fn get_chat_since(&self, message_id: u32) -> Vec<ChatMessage> {
+        return self.chat.get_since(message_id);
+    }
+
+    pub fn make_offer(&mut self, my_id: SeatId, offer: Offer) -> Result<()> {
+        self.get_seat(my_id)?;
+
+        if !self.can_offer(offer) {
+            return Err(anyhow!("cant make this offer, wrong stage"));
+        }
+
+        self.offer = Some((my_id, offer));
+        self.accepted.clear();
+
+        return Ok(());
+    }
+
+    pub fn accept_offer(&mut self, my_id: SeatId, offer: Offer) -> Result<()> {
+        self.get_seat(my_id)?;
+
+        match self.offer {
+            Some((offer_id, pending)) if offer_id != my_id && pending == offer => {}
+            _ => return Err(anyhow!("cant accept, no such offer")),
+        };
+
+        // the game may have moved on since, like a pause offered in a game that is over now
+        if !self.can_offer(offer) {
+            self.offer = None;
+            self.accepted.clear();
+            return Err(anyhow!("cant accept, offer has expired"));
+        }
+
+        if !self.accepted.contains(&my_id) {
+            self.accepted.push(my_id);
+        }
+
+        // an offer goes through once everyone still playing but the one who made it agrees
+        let connections = self.get_connections();
+        let agreed = self
+            .accepted
+            .iter()
+            .filter(|player_id| connections.contains(player_id))
+            .count();
+        if agreed + 1 < connections.len() {
+            return Ok(());
+        }
+
+        let armada = match offer {
+            Offer::Rematch => self.plan_armada()?,
+            _ => Vec::new(),
+        };
+
+        self.offer = None;
+        self.accepted.clear();
+
+        match offer {
+            Offer::Rematch => {
+                if let GameStage::SeriesWon(_) = self.stage {
+                    self.series.reset();
+                }
+
+                self.reset_players();
+                self.stage = GameStage::PlayerShips(None);
+                self.deploy_armada(armada);
+            }
+            Offer::Draw => {
+                self.stage = GameStage::DrawAgreed;
+            }
+            Offer::Pause => {
+                self.paused_stage = Some(self.stage);
+                self.stage = GameStage::Paused;
+            }
+            Offer::Resume => {
+                self.stage = self.paused_stage.take().unwrap_or(GameStage::Waiting);
+            }
+        };
+
+        return Ok(());
+    }
+
+    fn can_offer(&self, offer: Offer) -> bool {
+        return match offer {
+            Offer::Rematch => self.stage.is_finished(),
+            Offer::Draw => {
+                self.stage.is_playing() && !matches!(self.stage, GameStage::PlayerShips(_))
+            }
+            Offer::Pause => self.stage.is_playing(),
+            Offer::Resume => self.stage == GameStage::Paused,
+        };
+    }
+
+    pub fn resign(&mut self, my_id: SeatId) -> Result<()> {
+        if !self.stage.is_playing() && self.stage != GameStage::Paused {
+            return Err(anyhow!("cant resign, game is not running"));
+        }
+
+        return self.forfeit(my_id, GameStage::PlayerResigns(my_id));
+    }
+
+    // the player drops out, and if only one team is left standing the game is over,
+    // with the given outcome between two players
+    fn forfeit(&mut self, my_id: SeatId, outcome: GameStage) -> Result<()> {
+        let my_seat = self.get_seat(my_id)?;
+
+        self.seats[my_seat].has_resigned = true;
+        self.pending_shots
+            .retain(|(player_id, _, _)| *player_id != my_id);
+
+        let remaining = self.get_alive_teams();
+
+        if remaining.len() > 1 {
+            // a paused game moves on from where it stopped, and stays paused
+            let is_paused = self.stage == GameStage::Paused;
+            if is_paused {
+                self.stage = self.paused_stage.take().unwrap_or(GameStage::Waiting);
+            }
+
+            match self.stage {
+                GameStage::PlayerShips(_) => self.check_placement()?,
+                GameStage::PlayersShoot(_) => self.check_round()?,
+                GameStage::PlayerShoots(player_id) if player_id == my_id => {
+                    self.pass_turn(my_seat)?
+                }
+                _ => {}
+            };
+
+            if is_paused && self.stage.is_playing() {
+                self.paused_stage = Some(self.stage);
+                self.stage = GameStage::Paused;
+            }
+
+            return Ok(());
+        }
+
+        self.paused_stage = None;
+        self.offer = None;
+
+        if self.seats.len() == 2 {
+            self.stage = outcome;
+        } else {
+            let winner_team = *remaining.first().ok_or(anyhow!("no team left"))?;
+            self.stage = self.get_team_win(winner_team)?;
+        }
+
+        self.record_result();
 
         return Ok(());
     }
 
-    pub fn place_figure(&mut self, my_id: PlayerId, ship: Ship, point: Point) -> Result<()> {
+    pub fn place_figure(&mut self, my_id: SeatId, ship: Ship, point: Point) -> Result<()> {
         let stage = self.stage.clone();
-        let rules = self.rules.clone();
 
         if stage != GameStage::PlayerShips(None) && stage != GameStage::PlayerShips(Some(my_id)) {
             return Err(anyhow!("cant place ships, wrong stage"));
         }
 
-        let players = self.get_players(my_id)?;
-        if rules.can_place_ship(players.me, ship) {
-            players.me.place_figure(ship, point)?;
+        let my_seat = self.get_seat(my_id)?;
+        let rules = self.get_seat_rules(my_seat);
+        let me = &mut self.seats[my_seat].player;
+
+        if rules.can_place_ship(me, ship) {
+            me.place_figure(ship, point, rules.get_armor(ship))?;
         } else {
             return Err(anyhow!("cant place ships above limit"));
         }
 
-        if !rules.has_available_ships(players.me) {
-            if stage == GameStage::PlayerShips(None) {
-                self.stage = GameStage::PlayerShips(Some(players.enemy_id));
+        self.check_placement()?;
+
+        return Ok(());
+    }
+
+    fn check_placement(&mut self) -> Result<()> {
+        let rules = self.rules;
+
+        let placing: Vec<usize> = self
+            .get_alive_seats()
+            .into_iter()
+            .filter(|seat| {
+                self.get_seat_rules(*seat)
+                    .has_available_ships(&self.seats[*seat].player)
+            })
+            .collect();
+
+        if placing.len() == 1 {
+            self.stage = GameStage::PlayerShips(Some(self.get_seat_id(placing[0])?));
+        } else if placing.is_empty() {
+            if rules.simultaneous_turns {
+                self.stage = GameStage::PlayersShoot(None);
             } else {
-                self.stage = GameStage::PlayerShoots(self.connection_a.unwrap());
+                let first_id = self.choose_first_player()?;
+                self.stage = GameStage::PlayerShoots(first_id);
+                self.play_computer_turns()?;
             }
         }
 
         return Ok(());
     }
 
-    pub fn remove_figure(&mut self, my_id: PlayerId, point: Point) -> Result<()> {
+    pub fn remove_figure(&mut self, my_id: SeatId, point: Point) -> Result<()> {
         if self.stage != GameStage::PlayerShips(None)
             && self.stage != GameStage::PlayerShips(Some(my_id))
         {
             return Err(anyhow!("cant remove ships, wrong stage"));
         }
 
-        let players = self.get_players(my_id)?;
-        players.me.remove_figure(point)?;
+        let my_seat = self.get_seat(my_id)?;
+        self.seats[my_seat].player.remove_figure(point)?;
 
         return Ok(());
     }
 
-    pub fn shoot(&mut self, my_id: PlayerId, point: Point) -> Result<()> {
+    pub fn shoot(&mut self, my_id: SeatId, point: Point) -> Result<()> {
+        let target_seat = self
+            .get_single_target(my_id)?
+            .ok_or(anyhow!("cant shoot, choose a target"))?;
+
+        return self.shoot_at(my_id, target_seat, point);
+    }
+
+    pub fn shoot_at(&mut self, my_id: SeatId, target_seat: usize, point: Point) -> Result<()> {
+        let my_seat = self.get_seat(my_id)?;
+        let rules = self.get_seat_rules(my_seat);
+
+        self.check_target(my_seat, target_seat, Some(point), "shoot")?;
+
+        if !rules.has_available_shots(&self.seats[my_seat].player) {
+            return Err(anyhow!("cant shoot, out of ammunition"));
+        }
+
+        if self.stage == GameStage::PlayersShoot(None)
+            || self.stage == GameStage::PlayersShoot(Some(my_id))
+        {
+            return self.submit_shot(my_id, target_seat, point);
+        }
+
         if self.stage != GameStage::PlayerShoots(my_id) {
             return Err(anyhow!("cant shoot, wrong turn or stage"));
         }
 
-        let players = self.get_players(my_id)?;
-        players.enemy.register_shot(point);
+        let is_hit = self.fire(my_seat, target_seat, point);
+        self.turn_shots = self.turn_shots.saturating_add(1);
 
-        if players.enemy.has_ship_at(point) {
-            if !players.enemy.has_intact_ships() {
-                self.stage = GameStage::PlayerWins(players.my_id);
-            } else {
-                self.stage = GameStage::PlayerShoots(players.my_id);
-            }
+        let alive = self.get_alive_seats();
+
+        if self.get_alive_teams().len() == 1 {
+            self.stage = GameStage::PlayerWins(my_id);
+            self.record_result();
+        } else if !alive.iter().any(|seat| self.can_shoot(*seat)) {
+            self.stage = self.score_by_damage()?;
+            self.record_result();
+        } else if (is_hit || self.turn_shots < rules.shots_per_turn) && self.can_shoot(my_seat) {
+            self.stage = GameStage::PlayerShoots(my_id);
         } else {
-            self.stage = GameStage::PlayerShoots(players.enemy_id);
+            self.pass_turn(my_seat)?;
         }
 
         return Ok(());
     }
 
-    pub fn get_state(&mut self, my_id: PlayerId) -> Result<StateSnapshot> {
-        let players = self.get_players(my_id)?;
+    // the player at the head of the table hosts the room and evens out the seats
+    pub fn set_handicap(&mut self, my_id: SeatId, seat: usize, handicap: Handicap) -> Result<()> {
+        if self.get_seat(my_id)? != 0 {
+            return Err(anyhow!("cant set handicap, only the host can"));
+        }
 
-        return Ok(StateSnapshot {
-            my_ships: players.me.ships,
-            my_marks: players.me.shots,
-            enemy_marks: players.enemy.shots,
-            enemy_losses: players.enemy.get_hits(),
-        });
+        if self.stage != GameStage::Waiting && !self.stage.is_finished() {
+            return Err(anyhow!("cant set handicap, game is running"));
+        }
+
+        if seat >= self.seats.len() {
+            return Err(anyhow!("seat not found"));
+        }
+
+        // every player needs a fleet that can be sunk, and that fits on the board
+        let rules = handicap.apply(self.rules.get_seat_rules(seat));
+        if (1..=4).all(|size| rules.get_limit(size) == 0) {
+            return Err(anyhow!("cant set handicap, fleet would be empty"));
+        }
+        fleet::check_fit(&rules).map_err(|_| anyhow!("cant set handicap, fleet does not fit"))?;
+
+        self.seats[seat].handicap = handicap;
+
+        return Ok(());
     }
 
-    pub fn trigger_sync(&self) -> Result<()> {
-        if let Some(sender_a) = &self.sender_a {
-            sender_a.send(())?;
+    pub fn hint(&mut self, my_id: SeatId) -> Result<()> {
+        let target_seat = self
+            .get_single_target(my_id)?
+            .ok_or(anyhow!("cant take a hint, choose a target"))?;
+
+        return self.hint_at(my_id, target_seat);
+    }
+
+    // a hint reveals one of the enemy's ship cells that hasn't been found yet,
+    // without taking up the turn
+    pub fn hint_at(&mut self, my_id: SeatId, target_seat: usize) -> Result<()> {
+        let my_seat = self.get_seat(my_id)?;
+        let rules = self.get_seat_rules(my_seat);
+
+        if self.stage != GameStage::PlayerShoots(my_id) {
+            return Err(anyhow!("cant take a hint, wrong turn or stage"));
+        }
+
+        self.check_target(my_seat, target_seat, None, "take a hint")?;
+
+        if !rules.has_available_hints(&self.seats[my_seat].player) {
+            return Err(anyhow!("cant take a hint, no hints available"));
         }
-        if let Some(sender_b) = &self.sender_b {
-            sender_b.send(())?;
+
+        let target = &self.seats[target_seat].player;
+        let hidden: Vec<Point> = Point::all(target.board_size)
+            .filter(|point| {
+                target.has_ship_at(*point)
+                    && !target.shots.is_marked_field(*point)
+                    && !target.pings.is_marked_field(*point)
+            })
+            .collect();
+
+        if hidden.is_empty() {
+            return Err(anyhow!("cant take a hint, nothing left to reveal"));
         }
 
+        let point = hidden[self.random.next_below(hidden.len())];
+
+        self.seats[target_seat].player.pings.mark_field(point);
+        self.seats[my_seat].player.hints_used += 1;
+
         return Ok(());
     }
 
-    fn get_players(&mut self, my_id: PlayerId) -> Result<Players> {
-        if self.connection_a == Some(my_id) && self.connection_b.is_some() {
-            return Ok(Players {
-                my_id: self.connection_a.unwrap(),
-                me: &mut self.player_a,
-                enemy_id: self.connection_b.unwrap(),
-                enemy: &mut self.player_b,
-            });
-        } else if self.connection_b == Some(my_id) && self.connection_a.is_some() {
-            return Ok(Players {
-                my_id: self.connection_b.unwrap(),
-                me: &mut self.player_b,
-                enemy_id: self.connection_a.unwrap(),
-                enemy: &mut self.player_a,
-            });
-        } else {
-            return Err(anyhow!("player not found"));
+    pub fn sonar(&mut self, my_id: SeatId, point: Point) -> Result<()> {
+        let target_seat = self
+            .get_single_target(my_id)?
+            .ok_or(anyhow!("cant use sonar, choose a target"))?;
+
+        return self.sonar_at(my_id, target_seat, point);
+    }
+
+    pub fn sonar_at(&mut self, my_id: SeatId, target_seat: usize, point: Point) -> Result<()> {
+        let my_seat = self.get_seat(my_id)?;
+        let rules = self.get_seat_rules(my_seat);
+
+        if self.stage != GameStage::PlayerShoots(my_id) {
+            return Err(anyhow!("cant use sonar, wrong turn or stage"));
+        }
+
+        self.check_target(my_seat, target_seat, Some(point), "use sonar")?;
+
+        if !rules.has_available_sonar(&self.seats[my_seat].player) {
+            return Err(anyhow!("cant use sonar, no pings available"));
         }
+
+        self.seats[target_seat].player.register_ping(point);
+        self.seats[my_seat].player.sonar_pings += 1;
+
+        self.pass_turn(my_seat)?;
+
+        return Ok(());
     }
-}
 
-#[cfg(test)]
-mod test_game {
-    use anyhow::Result;
+    // shots, hints and sonar all go at an enemy still afloat, from a player still in the game,
+    // and at a point on the enemy's board
+    fn check_target(
+        &self,
+        my_seat: usize,
+        target_seat: usize,
+        point: Option<Point>,
+        action: &str,
+    ) -> Result<()> {
+        let is_enemy = self
+            .seats
+            .get(target_seat)
+            .is_some_and(|target| target.is_alive() && target.team != self.seats[my_seat].team);
 
-    use crate::game_rules::GameRules;
+        if !is_enemy {
+            return Err(anyhow!("cant {}, invalid target", action));
+        }
 
-    use super::Game;
+        if !self.seats[my_seat].is_alive() {
+            return Err(anyhow!("cant {}, you are out of the game", action));
+        }
 
-    #[test]
-    pub fn test_connect() -> Result<()> {
-        let rules = GameRules::new();
-        let mut game = Game::new(rules);
+        let board_size = self.seats[target_seat].player.board_size;
+        if point.is_some_and(|point| !point.is_on_board(board_size)) {
+            return Err(anyhow!("cant {}, point is off the board", action));
+        }
+
+        return Ok(());
+    }
 
-        let (connection_id, _) = game.connect()?;
-        assert_eq!(connection_id, 1);
+    fn submit_shot(&mut self, my_id: SeatId, target_seat: usize, point: Point) -> Result<()> {
+        let my_seat = self.get_seat(my_id)?;
+        if self.get_pending_count(my_seat) >= self.get_salvo_size(my_seat) {
+            return Err(anyhow!("cant shoot, already fired this round"));
+        }
 
-        let (connection_id, _) = game.connect()?;
-        assert_eq!(connection_id, 2);
+        self.pending_shots.push((my_id, target_seat, point));
 
-        assert!(game.connect().is_err());
+        return self.check_round();
+    }
+
+    fn check_round(&mut self) -> Result<()> {
+        for seat in self.get_alive_seats() {
+            while self.seats[seat].is_computer
+                && self.get_pending_count(seat) < self.get_salvo_size(seat)
+            {
+                let (target_seat, point) = self.aim(seat)?;
+                self.pending_shots
+                    .push((self.get_seat_id(seat)?, target_seat, point));
+            }
+        }
+
+        let waiting: Vec<usize> = self
+            .get_alive_seats()
+            .into_iter()
+            .filter(|seat| self.get_pending_count(*seat) < self.get_salvo_size(*seat))
+            .collect();
+
+        if waiting.len() == 1 {
+            self.stage = GameStage::PlayersShoot(Some(self.get_seat_id(waiting[0])?));
+        } else if waiting.is_empty() {
+            self.resolve_round()?;
+        }
 
         return Ok(());
     }
 
-    #[test]
-    pub fn test_disconnect() -> Result<()> {
-        let rules = GameRules::new();
-        let mut game = Game::new(rules);
+    fn resolve_round(&mut self) -> Result<()> {
+        for (my_id, target_seat, point) in std::mem::take(&mut self.pending_shots) {
+            let my_seat = self.get_seat(my_id)?;
+            self.fire(my_seat, target_seat, point);
+        }
 
-        let (connection_a, _) = game.connect()?;
-        let (connection_b, _) = game.connect()?;
+        self.round = self.round.saturating_add(1);
 
-        game.disconnect(connection_a)?;
+        let alive = self.get_alive_seats();
+        let alive_teams = self.get_alive_teams();
+
+        if alive_teams.is_empty() {
+            self.stage = GameStage::Draw;
+        } else if alive_teams.len() == 1 {
+            self.stage = self.get_team_win(alive_teams[0])?;
+        } else if let Some(survivor) = self.get_survivor() {
+            self.stage = GameStage::PlayerWins(self.get_seat_id(survivor)?);
+        } else if !alive.iter().any(|seat| self.can_shoot(*seat)) {
+            self.stage = self.score_by_damage()?;
+        } else {
+            self.stage = GameStage::PlayersShoot(None);
+        }
 
-        assert_eq!(game.connection_a, Some(connection_b));
-        assert_eq!(game.connection_b, None);
+        self.record_result();
 
         return Ok(());
     }
 
-    #[test]
-    pub fn test_trigger_sync() -> Result<()> {
-        let mut game = Game::new(GameRules::new());
+    fn fire(&mut self, my_seat: usize, target_seat: usize, point: Point) -> bool {
+        let target = &mut self.seats[target_seat].player;
+        let is_damaged = target.register_shot(point);
+        let is_hit = target.has_ship_at(point);
 
-        let (_, receiver_a) = game.connect()?;
-        let (_, receiver_b) = game.connect()?;
+        let me = &mut self.seats[my_seat].player;
+        me.shots_fired = me.shots_fired.saturating_add(1);
+        if is_damaged {
+            me.damage_dealt += 1;
+        }
 
-        game.trigger_sync()?;
+        return is_hit;
+    }
 
-        assert_eq!(receiver_a.try_iter().count(), 1);
-        assert_eq!(receiver_b.try_iter().count(), 1);
+    fn pass_turn(&mut self, my_seat: usize) -> Result<()> {
+        let team_count = self.team_turns.len();
+        let my_team = self.seats[my_seat].team;
+
+        self.team_turns[my_team] = my_seat;
+        self.turn_shots = 0;
+
+        // every turn counts towards the repair cooldown, whatever it was spent on
+        let me = &mut self.seats[my_seat].player;
+        me.repair_cooldown = me.repair_cooldown.saturating_sub(1);
+
+        // the turn goes to the next team around the table and then to the next
+        // player within it, skipping sunk, resigned and disarmed players
+        let next_seat = (1..=team_count)
+            .map(|offset| (my_team + offset) % team_count)
+            .find_map(|team| {
+                let count = self.seats.len();
+                let last_seat = self.team_turns[team];
+
+                return (1..=count)
+                    .map(|offset| (last_seat + offset) % count)
+                    .filter(|seat| self.seats[*seat].team == team)
+                    .find(|seat| self.seats[*seat].is_alive() && self.can_shoot(*seat));
+            })
+            .unwrap_or(my_seat);
+
+        // a round is over once the turn comes back to the team that opened the game
+        let first_team = self.first_player.and_then(|id| self.get_team(id).ok());
+        if first_team == Some(self.seats[next_seat].team) {
+            self.round = self.round.saturating_add(1);
+        }
+
+        if let Some(survivor) = self.get_survivor() {
+            self.stage = GameStage::PlayerWins(self.get_seat_id(survivor)?);
+            self.record_result();
+            return Ok(());
+        }
+
+        self.stage = GameStage::PlayerShoots(self.get_seat_id(next_seat)?);
+        self.play_computer_turns()?;
+
+        return Ok(());
+    }
+
+    // a seat that only has to survive wins once enough rounds are over,
+    // or once nobody left against it has the ammunition to sink it
+    fn get_survivor(&self) -> Option<usize> {
+        let alive = self.get_alive_seats();
+
+        return alive.iter().copied().find(|seat| {
+            let WinCondition::Survive(rounds) = self.rules.get_win_condition(*seat) else {
+                return false;
+            };
+
+            let is_threatened = alive.iter().any(|enemy| {
+                self.seats[*enemy].team != self.seats[*seat].team && self.can_shoot(*enemy)
+            });
+
+            return self.round >= rounds || !is_threatened;
+        });
+    }
+
+    // the armada's fleet is laid out before anything else changes, so that a fleet that
+    // doesn't fit leaves the game as it was
+    fn plan_armada(&mut self) -> Result<Vec<(usize, Player)>> {
+        let mut fleets = Vec::new();
+
+        for seat in 0..self.seats.len() {
+            if self.seats[seat].is_computer {
+                let armada_rules = self.get_seat_rules(seat);
+                fleets.push((
+                    seat,
+                    fleet::place_fitting_fleet(&armada_rules, &mut self.random)?,
+                ));
+            }
+        }
+
+        return Ok(fleets);
+    }
+
+    fn deploy_armada(&mut self, fleets: Vec<(usize, Player)>) {
+        for (seat, player) in fleets {
+            self.seats[seat].player = player;
+        }
+    }
+
+    fn play_computer_turns(&mut self) -> Result<()> {
+        while let GameStage::PlayerShoots(player_id) = self.stage {
+            let seat = self.get_seat(player_id)?;
+            if !self.seats[seat].is_computer || !self.can_shoot(seat) {
+                break;
+            }
+
+            let (target_seat, point) = self.aim(seat)?;
+            self.shoot_at(player_id, target_seat, point)?;
+        }
+
+        return Ok(());
+    }
+
+    // the computer fires at random, but comes back to finish off armored cells
+    fn aim(&mut self, my_seat: usize) -> Result<(usize, Point)> {
+        let my_team = self.seats[my_seat].team;
+
+        let targets: Vec<usize> = self
+            .get_alive_seats()
+            .into_iter()
+            .filter(|seat| self.seats[*seat].team != my_team)
+            .collect();
+
+        if targets.is_empty() {
+            return Err(anyhow!("no targets left"));
+        }
+
+        let target_seat = targets[self.random.next_below(targets.len())];
+        let target = &self.seats[target_seat].player;
+        let damaged = target.get_damaged();
+
+        let points: Vec<Point> = Point::all(target.board_size)
+            .filter(|point| {
+                !target.shots.is_marked_field(*point) || damaged.is_marked_field(*point)
+            })
+            .collect();
+
+        if points.is_empty() {
+            return Err(anyhow!("no points left to shoot at"));
+        }
+
+        let point = points[self.random.next_below(points.len())];
+
+        return Ok((target_seat, point));
+    }
+
+    // every team starts with its player sitting closest to the head of the table,
+    // so the rotation begins right after the last one
+    fn get_initial_team_turns(rules: &GameRules) -> Vec<usize> {
+        let seat_count = rules.get_seat_count();
+
+        return (0..rules.get_team_count())
+            .map(|team| {
+                (0..seat_count)
+                    .filter(|seat| rules.get_team(*seat) == team)
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+    }
+
+    fn choose_first_player(&mut self) -> Result<SeatId> {
+        let count = self.seats.len();
+
+        let first_seat = match self.rules.first_player {
+            FirstPlayer::FirstConnected => 0,
+            FirstPlayer::CoinFlip(_) => (self.random.next_u64() % count as u64) as usize,
+            FirstPlayer::PreviousLoser => match self.last_winner {
+                Some(winner_id) => (self.get_seat(winner_id)? + 1) % count,
+                None => 0,
+            },
+            FirstPlayer::Alternate => match self.first_player {
+                Some(first_id) => (self.get_seat(first_id)? + 1) % count,
+                None => 0,
+            },
+        };
+
+        let first_seat = (0..count)
+            .map(|offset| (first_seat + offset) % count)
+            .find(|seat| !self.seats[*seat].has_resigned)
+            .unwrap_or(first_seat);
+
+        let first_id = self.get_seat_id(first_seat)?;
+        self.first_player = Some(first_id);
+
+        return Ok(first_id);
+    }
+
+    fn record_result(&mut self) {
+        let winner_team = match self.stage {
+            GameStage::PlayerWins(winner_id) => self.get_team(winner_id).ok(),
+            GameStage::PlayerResigns(loser_id) | GameStage::PlayerAbandons(loser_id) => {
+                let loser_team = self.get_team(loser_id).ok();
+                self.seats
+                    .iter()
+                    .map(|seat| seat.team)
+                    .find(|team| Some(*team) != loser_team)
+            }
+            _ => None,
+        };
+
+        let Some(winner_team) = winner_team else {
+            return;
+        };
+
+        let winners: Vec<SeatId> = (0..self.seats.len())
+            .filter(|seat| self.seats[*seat].team == winner_team)
+            .map(|seat| SeatId(seat as u8))
+            .collect();
+
+        self.last_winner = match self.stage {
+            GameStage::PlayerWins(winner_id) => Some(winner_id),
+            _ => winners.first().copied(),
+        };
+
+        // single games keep a running score instead of ending a series
+        for winner_id in &winners {
+            self.series.record_win(*winner_id);
+        }
+
+        if let Some(winner_id) = self.last_winner {
+            if self.series.best_of > 1 && self.series.is_won_by(winner_id) {
+                self.stage = GameStage::SeriesWon(winner_id);
+            }
+        }
+    }
+
+    // every game of a series starts from scratch, only the seats, the score and whoever
+    // went first carry over
+    fn reset_players(&mut self) {
+        for seat in self.seats.iter_mut() {
+            seat.player = Player::with_size(seat.player.board_size);
+            seat.has_resigned = false;
+        }
+
+        self.team_turns = Game::get_initial_team_turns(&self.rules);
+        self.round = 0;
+        self.turn_shots = 0;
+
+        self.pending_shots.clear();
+        self.offer = None;
+        self.accepted.clear();
+        self.paused_stage = None;
+    }
+
+    // once everyone is out of ammunition, the team that inflicted the most damage wins
+    fn score_by_damage(&self) -> Result<GameStage> {
+        let alive_teams = self.get_alive_teams();
+
+        let scores: Vec<u16> = alive_teams
+            .iter()
+            .map(|team| {
+                self.seats
+                    .iter()
+                    .filter(|seat| seat.team == *team)
+                    .map(|seat| seat.player.damage_dealt)
+                    .sum()
+            })
+            .collect();
+
+        let best = scores.iter().max().copied().unwrap_or(0);
+
+        let leaders: Vec<usize> = alive_teams
+            .into_iter()
+            .zip(scores)
+            .filter(|(_, score)| *score == best)
+            .map(|(team, _)| team)
+            .collect();
+
+        if leaders.len() == 1 {
+            return self.get_team_win(leaders[0]);
+        } else {
+            return Ok(GameStage::Draw);
+        }
+    }
+
+    fn get_team_win(&self, team: usize) -> Result<GameStage> {
+        let winner_seat = (0..self.seats.len())
+            .filter(|seat| self.seats[*seat].team == team)
+            .min_by_key(|seat| !self.seats[*seat].is_alive())
+            .ok_or(anyhow!("team not found"))?;
+
+        return Ok(GameStage::PlayerWins(self.get_seat_id(winner_seat)?));
+    }
+
+    pub fn move_figure(&mut self, my_id: SeatId, point: Point, direction: Direction) -> Result<()> {
+        if !self.rules.mobile_fleet {
+            return Err(anyhow!("cant move ships, fleet is not mobile"));
+        }
+
+        if self.stage != GameStage::PlayerShoots(my_id) {
+            return Err(anyhow!("cant move ships, wrong turn or stage"));
+        }
+
+        let my_seat = self.get_seat(my_id)?;
+        self.seats[my_seat].player.move_figure(point, direction)?;
+
+        self.pass_turn(my_seat)?;
+
+        return Ok(());
+    }
+
+    pub fn repair(&mut self, my_id: SeatId, point: Point) -> Result<()> {
+        if self.stage != GameStage::PlayerShoots(my_id) {
+            return Err(anyhow!("cant repair, wrong turn or stage"));
+        }
+
+        let my_seat = self.get_seat(my_id)?;
+        let rules = self.get_seat_rules(my_seat);
+        let me = &mut self.seats[my_seat].player;
+
+        if !rules.can_repair(me) {
+            return Err(anyhow!("cant repair, no repairs available"));
+        }
+
+        me.repair_figure(point)?;
+
+        // the cooldown starts once the turn spent on the repair is over
+        self.pass_turn(my_seat)?;
+        self.seats[my_seat].player.repair_cooldown = rules.repair_cooldown;
+
+        return Ok(());
+    }
+
+    pub fn get_state(&mut self, my_id: SeatId) -> Result<StateSnapshot> {
+        let my_seat = self.get_seat(my_id)?;
+        let me = &self.seats[my_seat].player;
+
+        let turn_seat = match self.stage {
+            GameStage::PlayerShoots(player_id) => Some(self.get_seat(player_id)?),
+            _ => None,
+        };
+
+        let my_team = self.seats[my_seat].team;
+
+        let mut allies = Vec::new();
+        let mut enemies = Vec::new();
+        for (seat, enemy) in self.seats.iter().enumerate() {
+            if seat == my_seat {
+                continue;
+            }
+
+            if enemy.team == my_team {
+                allies.push(AllyView {
+                    seat,
+                    nickname: enemy.nickname.clone(),
+                    is_alive: enemy.is_alive(),
+                    ships: enemy.player.ships,
+                    marks: enemy.player.shots,
+                    damage: enemy.player.get_damaged(),
+                });
+                continue;
+            }
+
+            enemies.push(EnemyView {
+                seat,
+                nickname: enemy.nickname.clone(),
+                is_alive: enemy.is_alive(),
+                wins: self.series.get_wins(SeatId(seat as u8)),
+                marks: enemy.player.shots,
+                losses: enemy.player.get_hits(),
+                damage: enemy.player.get_damaged(),
+                pings: enemy.player.pings,
+            });
+        }
+
+        return Ok(StateSnapshot {
+            my_seat,
+            turn_seat,
+            my_ships: me.ships,
+            my_marks: me.shots,
+            my_damage: me.get_damaged(),
+            first_player: self.first_player,
+            best_of: self.series.best_of,
+            my_wins: self.series.get_wins(my_id),
+            round: self.round,
+            offer: self.offer,
+            allies,
+            enemies,
+        });
+    }
+
+    pub fn get_public_view(&self, reveal_ships: bool) -> Result<PublicView> {
+        let turn_seat = match self.stage {
+            GameStage::PlayerShoots(player_id) => Some(self.get_seat(player_id)?),
+            _ => None,
+        };
+
+        let players = self
+            .seats
+            .iter()
+            .enumerate()
+            .map(|(seat, player)| PlayerView {
+                seat,
+                nickname: player.nickname.clone(),
+                is_alive: player.is_alive(),
+                wins: self.series.get_wins(SeatId(seat as u8)),
+                ships: if reveal_ships {
+                    player.player.ships
+                } else {
+                    Playmap::with_size(player.player.board_size)
+                },
+                marks: player.player.shots,
+                losses: player.player.get_hits(),
+                damage: player.player.get_damaged(),
+            })
+            .collect();
+
+        return Ok(PublicView {
+            stage: self.stage,
+            turn_seat,
+            best_of: self.series.best_of,
+            round: self.round,
+            players,
+        });
+    }
+
+    // one entry per seat, none for empty seats and the computer
+    pub fn get_nicknames(&self) -> Vec<Option<Nickname>> {
+        return self
+            .seats
+            .iter()
+            .map(|seat| seat.nickname.clone())
+            .collect();
+    }
+
+    pub fn get_spectator_count(&self) -> usize {
+        return self.spectators.len();
+    }
+
+    pub fn get_allies(&self, my_id: SeatId) -> Result<Vec<SeatId>> {
+        let my_team = self.get_team(my_id)?;
+
+        return Ok((0..self.seats.len())
+            .filter(|seat| self.seats[*seat].team == my_team)
+            .map(|seat| SeatId(seat as u8))
+            .filter(|player_id| *player_id != my_id)
+            .collect());
+    }
+
+    // nobody is left once all players are gone for good, while those who dropped out
+    // still hold their seat as long as they may come back
+    pub fn is_empty(&self) -> bool {
+        let window = Duration::from_secs(self.rules.reconnect_window as u64);
+
+        return !self.seats.iter().any(|seat| {
+            seat.sender.is_some()
+                || (!seat.has_resigned
+                    && seat.disconnected_at.is_some_and(|at| at.elapsed() < window))
+        });
+    }
+
+    // a connection that went away without leaving yet doesn't hold up everyone else
+    pub fn trigger_sync(&self) -> Result<()> {
+        for seat in &self.seats {
+            if let Some(sender) = &seat.sender {
+                let _ = sender.send(());
+            }
+        }
+
+        for spectator in &self.spectators {
+            let _ = spectator.sender.send(());
+        }
+
+        return Ok(());
+    }
+
+    // every round a seat fires its shots per turn, or whatever ammunition it has left
+    fn get_salvo_size(&self, seat: usize) -> usize {
+        let rules = self.get_seat_rules(seat);
+        let shots_fired = self.seats[seat].player.shots_fired;
+
+        return match rules.shot_limit {
+            0 => rules.shots_per_turn as usize,
+            limit => rules.shots_per_turn.min(limit.saturating_sub(shots_fired)) as usize,
+        };
+    }
+
+    fn get_pending_count(&self, seat: usize) -> usize {
+        return self
+            .pending_shots
+            .iter()
+            .filter(|(id, _, _)| id.get_index() == seat)
+            .count();
+    }
+
+    fn can_shoot(&self, seat: usize) -> bool {
+        return self
+            .get_seat_rules(seat)
+            .has_available_shots(&self.seats[seat].player);
+    }
+
+    fn get_seat_rules(&self, seat: usize) -> GameRules {
+        return self.seats[seat]
+            .handicap
+            .apply(self.rules.get_seat_rules(seat));
+    }
+
+    fn get_single_target(&self, my_id: SeatId) -> Result<Option<usize>> {
+        let my_team = self.get_team(my_id)?;
+
+        let targets: Vec<usize> = self
+            .get_alive_seats()
+            .into_iter()
+            .filter(|seat| self.seats[*seat].team != my_team)
+            .collect();
+
+        return Ok(match targets[..] {
+            [target_seat] => Some(target_seat),
+            _ => None,
+        });
+    }
+
+    fn get_alive_seats(&self) -> Vec<usize> {
+        return (0..self.seats.len())
+            .filter(|seat| self.seats[*seat].is_alive())
+            .collect();
+    }
+
+    fn get_alive_teams(&self) -> Vec<usize> {
+        let mut teams: Vec<usize> = self
+            .get_alive_seats()
+            .into_iter()
+            .map(|seat| self.seats[seat].team)
+            .collect();
+
+        teams.sort();
+        teams.dedup();
+
+        return teams;
+    }
+
+    // players who left, dropped out or gave up have no say anymore
+    fn get_connections(&self) -> Vec<SeatId> {
+        return (0..self.seats.len())
+            .filter(|seat| !self.seats[*seat].is_computer)
+            .filter(|seat| self.seats[*seat].sender.is_some() && !self.seats[*seat].has_resigned)
+            .filter_map(|seat| self.get_seat_id(seat).ok())
+            .collect();
+    }
+
+    pub fn get_connection_id(&self, my_id: SeatId) -> Result<ConnectionId> {
+        return self.seats[self.get_seat(my_id)?]
+            .connection
+            .ok_or(anyhow!("seat is empty"));
+    }
+
+    pub fn find_seat(&self, connection_id: ConnectionId) -> Result<SeatId> {
+        let seat = self
+            .seats
+            .iter()
+            .position(|seat| seat.connection == Some(connection_id))
+            .ok_or(anyhow!("player not found"))?;
+
+        return self.get_seat_id(seat);
+    }
+
+    fn get_seat_id(&self, seat: usize) -> Result<SeatId> {
+        if self
+            .seats
+            .get(seat)
+            .is_none_or(|seat| seat.connection.is_none())
+        {
+            return Err(anyhow!("seat is empty"));
+        }
+
+        return Ok(SeatId(seat as u8));
+    }
+
+    fn get_team(&self, my_id: SeatId) -> Result<usize> {
+        return Ok(self.seats[self.get_seat(my_id)?].team);
+    }
+
+    fn get_seat(&self, my_id: SeatId) -> Result<usize> {
+        let seat = my_id.get_index();
+
+        if self
+            .seats
+            .get(seat)
+            .is_none_or(|seat| seat.connection.is_none())
+        {
+            return Err(anyhow!("player not found"));
+        }
+
+        return Ok(seat);
+    }
+}
+
+#[cfg(test)]
+mod test_game {
+    use anyhow::Result;
+
+    use crate::game_rules::{FirstPlayer, GameRules};
+    use crate::game_stage::GameStage;
+    use crate::handicap::Handicap;
+    use crate::identity::{ConnectionId, SeatId};
+    use crate::nickname::Nickname;
+    use crate::offer::Offer;
+    use crate::point::{Direction, Orientation, Point};
+    use crate::preset::Preset;
+    use crate::random::Random;
+    use crate::ship::Ship;
+
+    use super::Game;
+
+    #[test]
+    pub fn test_connect() -> Result<()> {
+        let rules = GameRules::new();
+        let mut game = Game::new(rules);
+
+        let (player_a, _) = game.connect()?;
+        assert_eq!(player_a, SeatId(0));
+
+        let (player_b, _) = game.connect()?;
+        assert_eq!(player_b, SeatId(1));
+        assert_ne!(
+            game.get_connection_id(player_a)?,
+            game.get_connection_id(player_b)?
+        );
+
+        assert!(game.connect().is_err());
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_connect_as() -> Result<()> {
+        let mut game = Game::new(GameRules::new());
+
+        let (player_a, _) =
+            game.connect_as(ConnectionId::new(), Nickname::try_from("Ahab".as_bytes())?)?;
+        assert!(game
+            .connect_as(ConnectionId::new(), Nickname::try_from("ahab".as_bytes())?)
+            .is_err());
+
+        let (player_b, _) = game.connect_as(
+            ConnectionId::new(),
+            Nickname::try_from("Ishmael".as_bytes())?,
+        )?;
+        let state = game.get_state(player_b)?;
+
+        assert_eq!(state.enemies[0].seat, player_a.get_index());
+        assert_eq!(state.enemies[0].nickname.as_ref().unwrap().as_str(), "Ahab");
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_disconnect() -> Result<()> {
+        let rules = GameRules {
+            player_count: 3,
+            ..GameRules::new()
+        };
+        let mut game = Game::new(rules);
+
+        let (player_a, _) = game.connect()?;
+        let (player_b, _) = game.connect()?;
+        let connection_b = game.get_connection_id(player_b)?;
+
+        game.disconnect(player_a)?;
+
+        // seats stay put, the one left behind is freed for the next player
+        assert_eq!(game.seats[0].connection, None);
+        assert_eq!(game.find_seat(connection_b)?, player_b);
+
+        let (player_c, _) = game.connect()?;
+        assert_eq!(player_c, player_a);
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_reconnect() -> Result<()> {
+        let mut game = Game::new(GameRules {
+            submarine_limit: 1,
+            destroyer_limit: 0,
+            cruiser_limit: 0,
+            battleship_limit: 0,
+            ..GameRules::new()
+        });
+
+        let (player_a, _) = game.connect()?;
+        let (player_b, _) = game.connect()?;
+
+        game.place_figure(player_a, Ship::Submarine, Point::new(0, 0)?)?;
+        game.place_figure(player_b, Ship::Submarine, Point::new(0, 0)?)?;
+
+        let token = game.get_session_token(player_a)?;
+        game.disconnect(player_a)?;
+
+        assert_eq!(game.stage, GameStage::PlayerShoots(player_a));
+        assert!(game.reconnect(ConnectionId::new(), token + 1).is_err());
+
+        let (player_id, _) = game.reconnect(ConnectionId::new(), token)?;
+
+        assert_eq!(player_id, player_a);
+        assert_eq!(game.seats[0].player.ships.value, 0b1000 << 124);
+        assert!(game.reconnect(ConnectionId::new(), token).is_err());
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_expire_sessions() -> Result<()> {
+        let mut game = Game::new(GameRules {
+            reconnect_window: 0,
+            ..GameRules::new()
+        });
+
+        let (player_a, _) = game.connect()?;
+        let (_, _receiver) = game.connect()?;
+
+        let token = game.get_session_token(player_a)?;
+        game.disconnect(player_a)?;
+
+        assert_eq!(game.expire_sessions()?, true);
+        assert_eq!(game.stage, GameStage::PlayerAbandons(player_a));
+        assert_eq!(game.expire_sessions()?, false);
+        assert!(game.reconnect(ConnectionId::new(), token).is_err());
+
+        // with nobody left to expire the sessions, coming back does it
+        let mut game = Game::new(GameRules {
+            reconnect_window: 0,
+            ..GameRules::new()
+        });
+
+        let (player_a, _) = game.connect()?;
+        let (player_b, _) = game.connect()?;
+
+        let token = game.get_session_token(player_a)?;
+        game.disconnect(player_a)?;
+        game.disconnect(player_b)?;
+
+        assert!(game.reconnect(ConnectionId::new(), token).is_err());
+        assert_eq!(game.stage, GameStage::PlayerAbandons(player_a));
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_leave() -> Result<()> {
+        let mut game = Game::new(GameRules {
+            submarine_limit: 1,
+            destroyer_limit: 0,
+            cruiser_limit: 0,
+            battleship_limit: 0,
+            ..GameRules::new()
+        });
+
+        let (player_a, _) = game.connect()?;
+        let (player_b, _receiver) = game.connect()?;
+
+        game.place_figure(player_a, Ship::Submarine, Point::new(0, 0)?)?;
+        game.leave(player_a)?;
+
+        assert_eq!(game.stage, GameStage::PlayerAbandons(player_a));
+        assert_eq!(game.stage.try_into_u8(player_b, &[])?, 18);
+        assert_eq!(game.series.get_wins(player_b), 1);
+        assert_eq!(game.seats[0].player.ships.value, 0b1000 << 124);
+
+        let (player_c, _) = game.connect()?;
+
+        assert_eq!(player_c, player_a);
+        assert_eq!(game.seats[0].player.ships.value, 0);
+        assert!(game.get_connection_id(player_b).is_ok());
+        assert_eq!(game.stage, GameStage::PlayerShips(None));
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_trigger_sync() -> Result<()> {
+        let mut game = Game::new(GameRules::new());
+
+        let (_, receiver_a) = game.connect()?;
+        let (_, receiver_b) = game.connect()?;
+
+        game.trigger_sync()?;
+
+        assert_eq!(receiver_a.try_iter().count(), 1);
+        assert_eq!(receiver_b.try_iter().count(), 1);
+
+        drop(receiver_b);
+        game.trigger_sync()?;
+
+        assert_eq!(receiver_a.try_iter().count(), 1);
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_spectate() -> Result<()> {
+        let mut game = Game::new(GameRules {
+            spectator_limit: 1,
+            submarine_limit: 1,
+            destroyer_limit: 0,
+            cruiser_limit: 0,
+            battleship_limit: 0,
+            ..GameRules::new()
+        });
+
+        let connection_id = ConnectionId::new();
+        let receiver = game.spectate(connection_id)?;
+        assert!(game.spectate(ConnectionId::new()).is_err());
+
+        let (player_a, _receiver_a) = game.connect()?;
+        let (player_b, _receiver_b) = game.connect()?;
+
+        game.place_figure(player_a, Ship::Submarine, Point::new(0, 0)?)?;
+        game.place_figure(player_b, Ship::Submarine, Point::new(1, 1)?)?;
+        game.shoot(player_a, Point::new(1, 1)?)?;
+        game.trigger_sync()?;
+
+        assert_eq!(receiver.try_iter().count(), 2);
+
+        let view = game.get_public_view(false)?;
+        assert_eq!(view.stage, GameStage::PlayerWins(player_a));
+        assert_eq!(view.players[0].ships.value, 0);
+        assert_ne!(view.players[1].losses.value, 0);
+
+        let view = game.get_public_view(true)?;
+        assert_eq!(view.players[0].ships.value, 0b1000 << 124);
+
+        game.stop_spectating(connection_id);
+        assert!(game.spectate(ConnectionId::new()).is_ok());
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_shoot_armored() -> Result<()> {
+        let mut game = Game::new(GameRules {
+            submarine_limit: 1,
+            destroyer_limit: 0,
+            cruiser_limit: 0,
+            battleship_limit: 0,
+            submarine_armor: 2,
+            ..GameRules::new()
+        });
+
+        let (player_a, _) = game.connect()?;
+        let (player_b, _) = game.connect()?;
+
+        game.place_figure(player_a, Ship::Submarine, Point::new(0, 0)?)?;
+        game.place_figure(player_b, Ship::Submarine, Point::new(0, 0)?)?;
+
+        game.shoot(player_a, Point::new(0, 0)?)?;
+
+        let state = game.get_state(player_a)?;
+        assert_eq!(state.enemies[0].damage.value, 0b1000 << 124);
+        assert_eq!(state.enemies[0].losses.value, 0b0000 << 124);
+        assert_eq!(game.stage, GameStage::PlayerShoots(player_a));
+
+        game.shoot(player_a, Point::new(0, 0)?)?;
+
+        let state = game.get_state(player_a)?;
+        assert_eq!(state.enemies[0].damage.value, 0b0000 << 124);
+        assert_eq!(state.enemies[0].losses.value, 0b1000 << 124);
+        assert_eq!(game.stage, GameStage::PlayerWins(player_a));
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_repair() -> Result<()> {
+        let mut game = Game::new(GameRules {
+            submarine_limit: 0,
+            destroyer_limit: 1,
+            cruiser_limit: 0,
+            battleship_limit: 0,
+            repair_limit: 2,
+            repair_cooldown: 1,
+            ..GameRules::new()
+        });
+
+        let (player_a, _) = game.connect()?;
+        let (player_b, _) = game.connect()?;
+
+        let destroyer = Ship::Destroyer(Orientation::Horizontal);
+        game.place_figure(player_a, destroyer, Point::new(0, 0)?)?;
+        game.place_figure(player_b, destroyer, Point::new(0, 0)?)?;
+
+        game.shoot(player_a, Point::new(0, 0)?)?;
+        game.shoot(player_a, Point::new(5, 5)?)?;
+
+        game.repair(player_b, Point::new(0, 0)?)?;
+
+        let state = game.get_state(player_a)?;
+        assert_eq!(state.enemies[0].losses.value, 0b0000 << 124);
+        assert_eq!(game.stage, GameStage::PlayerShoots(player_a));
+
+        game.shoot(player_a, Point::new(0, 0)?)?;
+        game.shoot(player_a, Point::new(5, 6)?)?;
+
+        assert!(game.repair(player_b, Point::new(0, 0)?).is_err());
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_repair_cooldown() -> Result<()> {
+        let mut game = Game::new(GameRules {
+            submarine_limit: 1,
+            destroyer_limit: 1,
+            cruiser_limit: 0,
+            battleship_limit: 0,
+            repair_limit: 2,
+            repair_cooldown: 1,
+            mobile_fleet: true,
+            ..GameRules::new()
+        });
+
+        let (player_a, _) = game.connect()?;
+        let (player_b, _) = game.connect()?;
+
+        let destroyer = Ship::Destroyer(Orientation::Horizontal);
+        for player in [player_a, player_b] {
+            game.place_figure(player, Ship::Submarine, Point::new(9, 9)?)?;
+            game.place_figure(player, destroyer, Point::new(0, 0)?)?;
+        }
+
+        game.shoot(player_a, Point::new(0, 0)?)?;
+        game.shoot(player_a, Point::new(5, 5)?)?;
+        game.repair(player_b, Point::new(0, 0)?)?;
+
+        game.shoot(player_a, Point::new(0, 0)?)?;
+        game.shoot(player_a, Point::new(5, 6)?)?;
+        assert!(game.repair(player_b, Point::new(0, 0)?).is_err());
+
+        // a turn spent moving a ship counts towards the cooldown just as well
+        game.move_figure(player_b, Point::new(9, 9)?, Direction::Up)?;
+        game.shoot(player_a, Point::new(5, 7)?)?;
+        game.repair(player_b, Point::new(0, 0)?)?;
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_move_figure() -> Result<()> {
+        let mut game = Game::new(GameRules {
+            submarine_limit: 1,
+            destroyer_limit: 0,
+            cruiser_limit: 0,
+            battleship_limit: 0,
+            mobile_fleet: true,
+            ..GameRules::new()
+        });
+
+        let (player_a, _) = game.connect()?;
+        let (player_b, _) = game.connect()?;
+
+        game.place_figure(player_a, Ship::Submarine, Point::new(0, 0)?)?;
+        game.place_figure(player_b, Ship::Submarine, Point::new(1, 0)?)?;
+
+        game.shoot(player_a, Point::new(0, 0)?)?;
+        game.move_figure(player_b, Point::new(1, 0)?, Direction::Left)?;
+
+        let state = game.get_state(player_a)?;
+        assert_eq!(state.enemies[0].marks.value, 0b1000 << 124);
+        assert_eq!(state.enemies[0].losses.value, 0b0000 << 124);
+        assert_eq!(game.stage, GameStage::PlayerShoots(player_a));
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_armada() -> Result<()> {
+        let mut game = Game::new(GameRules {
+            submarine_limit: 1,
+            destroyer_limit: 1,
+            cruiser_limit: 0,
+            battleship_limit: 0,
+            armada_size: 2,
+            first_player: FirstPlayer::Alternate,
+            ..GameRules::new()
+        });
+        game.random = Random::new(42);
+
+        let (player_a, _) = game.connect()?;
+        let (player_b, _) = game.connect()?;
+
+        assert_eq!(game.stage, GameStage::PlayerShips(None));
+        assert_eq!(game.seats[2].player.submarines, 2);
+        assert_eq!(game.seats[2].player.destroyers, 2);
+
+        for player in [player_a, player_b] {
+            game.place_figure(player, Ship::Submarine, Point::new(0, 0)?)?;
+            game.place_figure(
+                player,
+                Ship::Destroyer(Orientation::Horizontal),
+                Point::new(5, 5)?,
+            )?;
+        }
+
+        assert_eq!(game.stage, GameStage::PlayerShoots(player_a));
+
+        let state = game.get_state(player_a)?;
+        assert_eq!(state.allies.len(), 1);
+        assert_eq!(state.enemies.len(), 1);
+
+        // both players aim at open water, wherever the armada put its fleet
+        let armada_ships = game.seats[2].player.ships;
+        let misses: Vec<Point> = Point::all(armada_ships.board_size)
+            .filter(|point| !armada_ships.is_marked_field(*point))
+            .collect();
+
+        // after both players miss, the armada fires until it misses too
+        game.shoot(player_a, misses[0])?;
+        game.shoot(player_b, misses[1])?;
+
+        assert_eq!(game.seats[2].player.shots.count(), 2);
+        assert!(game.seats[0].player.shots.value + game.seats[1].player.shots.value > 0);
+        assert_eq!(game.stage, GameStage::PlayerShoots(player_a));
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_armada_does_not_fit() -> Result<()> {
+        for seed in 0..10 {
+            let mut game = Game::new(Preset::CoOp.get_rules());
+            game.random = Random::new(seed);
+            game.connect()?;
+            game.connect()?;
+
+            assert_eq!(game.seats[2].player.ships.count(), 40);
+        }
+
+        let mut game = Game::new(GameRules {
+            armada_size: 8,
+            ..GameRules::new()
+        });
+
+        let (player_a, _) = game.connect()?;
+        assert!(game.connect().is_err());
+
+        // the failed connection leaves no trace behind
+        assert_eq!(game.stage, GameStage::Waiting);
+        assert_eq!(game.seats[1].connection, None);
+        assert_eq!(game.seats[2].player.ships.count(), 0);
+        assert!(game.get_connection_id(player_a).is_ok());
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_convoy() -> Result<()> {
+        let mut game = Game::new(GameRules {
+            submarine_limit: 1,
+            destroyer_limit: 1,
+            cruiser_limit: 0,
+            battleship_limit: 0,
+            shot_limit: 5,
+            sonar_limit: 1,
+            convoy_rounds: 2,
+            ..GameRules::new()
+        });
+
+        let (defender, _) = game.connect()?;
+        let (attacker, _) = game.connect()?;
+
+        game.place_figure(defender, Ship::Submarine, Point::new(0, 0)?)?;
+        game.place_figure(
+            defender,
+            Ship::Destroyer(Orientation::Horizontal),
+            Point::new(5, 5)?,
+        )?;
+
+        assert!(game
+            .place_figure(
+                attacker,
+                Ship::Destroyer(Orientation::Horizontal),
+                Point::new(5, 5)?,
+            )
+            .is_err());
+
+        game.place_figure(attacker, Ship::Submarine, Point::new(9, 9)?)?;
+
+        assert_eq!(game.stage, GameStage::PlayerShoots(defender));
+
+        game.shoot(defender, Point::new(0, 0)?)?;
+        assert!(game.sonar(defender, Point::new(8, 8)?).is_err());
+
+        game.sonar(attacker, Point::new(1, 1)?)?;
+        assert!(game.sonar(attacker, Point::new(1, 1)?).is_err());
+
+        let state = game.get_state(attacker)?;
+        assert_eq!(state.enemies[0].pings.value, 0b1000 << 124);
+        assert_eq!(state.round, 1);
+
+        game.shoot(defender, Point::new(1, 0)?)?;
+        game.shoot(attacker, Point::new(0, 0)?)?;
+        game.shoot(attacker, Point::new(1, 1)?)?;
+
+        assert_eq!(game.stage, GameStage::PlayerWins(defender));
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_handicap() -> Result<()> {
+        let mut game = Game::new(GameRules {
+            submarine_limit: 1,
+            destroyer_limit: 0,
+            cruiser_limit: 0,
+            battleship_limit: 0,
+            ..GameRules::new()
+        });
+
+        let (player_a, _) = game.connect()?;
+
+        let handicap = Handicap {
+            submarine_bonus: 1,
+            extra_shots: 1,
+            hints: 1,
+            ..Handicap::new()
+        };
+        game.set_handicap(player_a, 1, handicap)?;
+
+        let sunk_already = Handicap {
+            submarine_bonus: -1,
+            ..Handicap::new()
+        };
+        let overcrowded = Handicap {
+            submarine_bonus: 100,
+            ..Handicap::new()
+        };
+        assert!(game.set_handicap(player_a, 1, sunk_already).is_err());
+        assert!(game.set_handicap(player_a, 1, overcrowded).is_err());
+        assert!(game.set_handicap(player_a, 2, Handicap::new()).is_err());
+
+        let (player_b, _) = game.connect()?;
+        assert!(game.set_handicap(player_b, 1, Handicap::new()).is_err());
+
+        game.place_figure(player_a, Ship::Submarine, Point::new(0, 0)?)?;
+        game.place_figure(player_b, Ship::Submarine, Point::new(0, 0)?)?;
+
+        assert_eq!(game.stage, GameStage::PlayerShips(Some(player_b)));
+        assert!(game.set_handicap(player_a, 1, Handicap::new()).is_err());
+
+        game.place_figure(player_b, Ship::Submarine, Point::new(5, 5)?)?;
+
+        assert!(game.hint(player_a).is_err());
+        game.shoot(player_a, Point::new(9, 9)?)?;
+
+        game.hint(player_b)?;
+        assert!(game.hint(player_b).is_err());
+
+        let state = game.get_state(player_b)?;
+        assert_eq!(state.enemies[0].pings.value, 0b1000 << 124);
+
+        game.shoot(player_b, Point::new(9, 9)?)?;
+        assert_eq!(game.stage, GameStage::PlayerShoots(player_b));
+
+        game.shoot(player_b, Point::new(8, 9)?)?;
+        assert_eq!(game.stage, GameStage::PlayerShoots(player_a));
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_extra_shots() -> Result<()> {
+        let mut game = Game::new(GameRules {
+            submarine_limit: 2,
+            destroyer_limit: 0,
+            cruiser_limit: 0,
+            battleship_limit: 0,
+            shots_per_turn: 2,
+            ..GameRules::new()
+        });
+
+        let (player_a, _) = game.connect()?;
+        let (player_b, _) = game.connect()?;
+
+        game.place_figure(player_a, Ship::Submarine, Point::new(0, 0)?)?;
+        game.place_figure(player_a, Ship::Submarine, Point::new(5, 5)?)?;
+        game.place_figure(player_b, Ship::Submarine, Point::new(0, 0)?)?;
+        game.place_figure(player_b, Ship::Submarine, Point::new(5, 5)?)?;
+
+        // a hit counts as one of the shots of the turn
+        game.shoot(player_a, Point::new(0, 0)?)?;
+        assert_eq!(game.stage, GameStage::PlayerShoots(player_a));
+
+        game.shoot(player_a, Point::new(9, 9)?)?;
+        assert_eq!(game.stage, GameStage::PlayerShoots(player_b));
+
+        // but the turn goes on as long as the shots keep hitting
+        game.shoot(player_b, Point::new(9, 9)?)?;
+        game.shoot(player_b, Point::new(0, 0)?)?;
+        assert_eq!(game.stage, GameStage::PlayerShoots(player_b));
+
+        game.shoot(player_b, Point::new(8, 8)?)?;
+        assert_eq!(game.stage, GameStage::PlayerShoots(player_a));
+
+        // in a salvo every player fires all their shots before the round is resolved
+        let mut game = Game::new(GameRules {
+            simultaneous_turns: true,
+            ..game.rules
+        });
+
+        let (player_a, _) = game.connect()?;
+        let (player_b, _) = game.connect()?;
+
+        game.place_figure(player_a, Ship::Submarine, Point::new(0, 0)?)?;
+        game.place_figure(player_a, Ship::Submarine, Point::new(5, 5)?)?;
+        game.place_figure(player_b, Ship::Submarine, Point::new(0, 0)?)?;
+        game.place_figure(player_b, Ship::Submarine, Point::new(5, 5)?)?;
+
+        game.shoot(player_a, Point::new(9, 9)?)?;
+        assert_eq!(game.stage, GameStage::PlayersShoot(None));
+
+        game.shoot(player_a, Point::new(8, 8)?)?;
+        assert_eq!(game.stage, GameStage::PlayersShoot(Some(player_b)));
+        assert!(game.shoot(player_a, Point::new(7, 7)?).is_err());
+
+        game.shoot(player_b, Point::new(9, 9)?)?;
+        game.shoot(player_b, Point::new(8, 8)?)?;
+        assert_eq!(game.stage, GameStage::PlayersShoot(None));
+        assert!(game.pending_shots.is_empty());
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_shoot_out_of_ammunition() -> Result<()> {
+        let mut game = Game::new(GameRules {
+            submarine_limit: 2,
+            destroyer_limit: 0,
+            cruiser_limit: 0,
+            battleship_limit: 0,
+            shot_limit: 2,
+            ..GameRules::new()
+        });
+
+        let (player_a, _) = game.connect()?;
+        let (player_b, _) = game.connect()?;
+
+        game.place_figure(player_a, Ship::Submarine, Point::new(0, 0)?)?;
+        game.place_figure(player_a, Ship::Submarine, Point::new(5, 5)?)?;
+        game.place_figure(player_b, Ship::Submarine, Point::new(0, 0)?)?;
+        game.place_figure(player_b, Ship::Submarine, Point::new(5, 5)?)?;
+
+        game.shoot(player_a, Point::new(0, 0)?)?;
+        game.shoot(player_a, Point::new(1, 1)?)?;
+
+        assert!(game.shoot(player_a, Point::new(2, 2)?).is_err());
+        assert_eq!(game.stage, GameStage::PlayerShoots(player_b));
+
+        game.shoot(player_b, Point::new(0, 0)?)?;
+        game.shoot(player_b, Point::new(1, 1)?)?;
+
+        assert_eq!(game.stage, GameStage::Draw);
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_shoot_without_limit() -> Result<()> {
+        let mut game = Game::new(GameRules {
+            submarine_limit: 1,
+            destroyer_limit: 0,
+            cruiser_limit: 0,
+            battleship_limit: 0,
+            ..GameRules::new()
+        });
+
+        let (player_a, _) = game.connect()?;
+        let (player_b, _) = game.connect()?;
+
+        game.place_figure(player_a, Ship::Submarine, Point::new(0, 0)?)?;
+        game.place_figure(player_b, Ship::Submarine, Point::new(0, 0)?)?;
+
+        // repeat shots and rounds keep counting, without any limit to run into
+        game.seats[0].player.shots_fired = u8::MAX;
+        game.shoot(player_a, Point::new(5, 5)?)?;
+
+        assert_eq!(game.seats[0].player.shots_fired, u8::MAX);
+        assert_eq!(game.stage, GameStage::PlayerShoots(player_b));
+
+        game.round = u8::MAX;
+        game.shoot(player_b, Point::new(5, 5)?)?;
+
+        assert_eq!(game.round, u8::MAX);
+        assert_eq!(game.stage, GameStage::PlayerShoots(player_a));
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_shoot_simultaneously() -> Result<()> {
+        let mut game = Game::new(GameRules {
+            submarine_limit: 1,
+            destroyer_limit: 0,
+            cruiser_limit: 0,
+            battleship_limit: 0,
+            simultaneous_turns: true,
+            ..GameRules::new()
+        });
+
+        let (player_a, _) = game.connect()?;
+        let (player_b, _) = game.connect()?;
+
+        game.place_figure(player_a, Ship::Submarine, Point::new(0, 0)?)?;
+        game.place_figure(player_b, Ship::Submarine, Point::new(1, 1)?)?;
+
+        assert_eq!(game.stage, GameStage::PlayersShoot(None));
+
+        game.shoot(player_a, Point::new(0, 0)?)?;
+
+        assert_eq!(game.stage, GameStage::PlayersShoot(Some(player_b)));
+        assert!(game.shoot(player_a, Point::new(1, 1)?).is_err());
+        assert_eq!(game.get_state(player_b)?.my_marks.value, 0);
+
+        game.shoot(player_b, Point::new(2, 2)?)?;
+
+        assert_eq!(game.stage, GameStage::PlayersShoot(None));
+        assert_eq!(game.get_state(player_b)?.my_marks.value, 0b1000 << 124);
+
+        game.shoot(player_b, Point::new(0, 0)?)?;
+        game.shoot(player_a, Point::new(1, 1)?)?;
+
+        assert_eq!(game.stage, GameStage::Draw);
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_shoot_simultaneously_free_for_all() -> Result<()> {
+        let mut game = Game::new(GameRules {
+            player_count: 3,
+            submarine_limit: 1,
+            destroyer_limit: 0,
+            cruiser_limit: 0,
+            battleship_limit: 0,
+            simultaneous_turns: true,
+            ..GameRules::new()
+        });
+
+        let (player_a, _) = game.connect()?;
+        let (player_b, _) = game.connect()?;
+        let (player_c, _) = game.connect()?;
+
+        for player in [player_a, player_b, player_c] {
+            game.place_figure(player, Ship::Submarine, Point::new(0, 0)?)?;
+        }
+
+        game.shoot_at(player_a, 1, Point::new(5, 5)?)?;
+
+        assert_eq!(game.stage, GameStage::PlayersShoot(None));
+        assert!(game.shoot_at(player_a, 2, Point::new(6, 6)?).is_err());
+        assert_eq!(game.pending_shots.len(), 1);
+
+        game.resign(player_c)?;
+
+        assert!(game.shoot_at(player_c, 0, Point::new(0, 0)?).is_err());
+        assert_eq!(game.stage, GameStage::PlayersShoot(Some(player_b)));
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_first_player_coin_flip() -> Result<()> {
+        let rules = GameRules {
+            submarine_limit: 1,
+            destroyer_limit: 0,
+            cruiser_limit: 0,
+            battleship_limit: 0,
+            first_player: FirstPlayer::CoinFlip(7),
+            ..GameRules::new()
+        };
+
+        let mut first_ids = Vec::new();
+        for _ in 0..2 {
+            let mut game = Game::new(rules);
+
+            let (player_a, _) = game.connect()?;
+            let (player_b, _) = game.connect()?;
+
+            game.place_figure(player_a, Ship::Submarine, Point::new(0, 0)?)?;
+            game.place_figure(player_b, Ship::Submarine, Point::new(0, 0)?)?;
+
+            let state = game.get_state(player_a)?;
+            assert_eq!(
+                game.stage,
+                GameStage::PlayerShoots(state.first_player.unwrap())
+            );
+
+            first_ids.push(state.first_player);
+        }
+
+        assert_eq!(first_ids[0], first_ids[1]);
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_rematch() -> Result<()> {
+        let mut game = Game::new(GameRules {
+            submarine_limit: 1,
+            destroyer_limit: 0,
+            cruiser_limit: 0,
+            battleship_limit: 0,
+            first_player: FirstPlayer::Alternate,
+            ..GameRules::new()
+        });
+
+        let (player_a, _) = game.connect()?;
+        let (player_b, _) = game.connect()?;
+
+        assert!(game.make_offer(player_a, Offer::Rematch).is_err());
+
+        game.place_figure(player_a, Ship::Submarine, Point::new(0, 0)?)?;
+        game.place_figure(player_b, Ship::Submarine, Point::new(0, 0)?)?;
+
+        assert_eq!(game.stage, GameStage::PlayerShoots(player_a));
+
+        game.shoot(player_a, Point::new(0, 0)?)?;
+
+        assert_eq!(game.stage, GameStage::PlayerWins(player_a));
+
+        game.make_offer(player_b, Offer::Rematch)?;
+
+        assert!(game.accept_offer(player_b, Offer::Rematch).is_err());
+
+        game.accept_offer(player_a, Offer::Rematch)?;
+
+        assert_eq!(game.stage, GameStage::PlayerShips(None));
+
+        let state = game.get_state(player_b)?;
+        assert_eq!(state.my_ships.value, 0);
+        assert_eq!(state.my_wins, 0);
+        assert_eq!(state.enemies[0].wins, 1);
+        assert_eq!(state.offer, None);
+
+        game.place_figure(player_a, Ship::Submarine, Point::new(0, 0)?)?;
+        game.place_figure(player_b, Ship::Submarine, Point::new(0, 0)?)?;
+
+        assert_eq!(game.stage, GameStage::PlayerShoots(player_b));
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_series() -> Result<()> {
+        let mut game = Game::new(GameRules {
+            submarine_limit: 1,
+            destroyer_limit: 0,
+            cruiser_limit: 0,
+            battleship_limit: 0,
+            series_length: 3,
+            ..GameRules::new()
+        });
+
+        let (player_a, _) = game.connect()?;
+        let (player_b, _) = game.connect()?;
+
+        for _ in 0..2 {
+            game.place_figure(player_a, Ship::Submarine, Point::new(0, 0)?)?;
+            game.place_figure(player_b, Ship::Submarine, Point::new(0, 0)?)?;
+            game.shoot(player_a, Point::new(0, 0)?)?;
+
+            if game.stage == GameStage::PlayerWins(player_a) {
+                game.make_offer(player_a, Offer::Rematch)?;
+                game.accept_offer(player_b, Offer::Rematch)?;
+            }
+        }
+
+        assert_eq!(game.stage, GameStage::SeriesWon(player_a));
+        assert_eq!(game.get_state(player_b)?.enemies[0].wins, 2);
+
+        game.make_offer(player_a, Offer::Rematch)?;
+        game.accept_offer(player_b, Offer::Rematch)?;
+
+        assert_eq!(game.get_state(player_b)?.enemies[0].wins, 0);
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_series_reset() -> Result<()> {
+        let mut game = Game::new(GameRules {
+            submarine_limit: 1,
+            destroyer_limit: 0,
+            cruiser_limit: 0,
+            battleship_limit: 0,
+            shots_per_turn: 2,
+            series_length: 3,
+            ..GameRules::new()
+        });
+
+        let (player_a, _) = game.connect()?;
+        let (player_b, _) = game.connect()?;
+
+        game.place_figure(player_a, Ship::Submarine, Point::new(0, 0)?)?;
+        game.place_figure(player_b, Ship::Submarine, Point::new(0, 0)?)?;
+
+        game.make_offer(player_b, Offer::Pause)?;
+        game.shoot(player_a, Point::new(5, 5)?)?;
+        game.shoot(player_a, Point::new(0, 0)?)?;
+
+        assert_eq!(game.stage, GameStage::PlayerWins(player_a));
+
+        game.make_offer(player_a, Offer::Rematch)?;
+        game.accept_offer(player_b, Offer::Rematch)?;
+
+        assert_eq!(game.turn_shots, 0);
+        assert_eq!(game.round, 0);
+        assert_eq!(game.offer, None);
+        assert_eq!(game.paused_stage, None);
+        assert!(game.pending_shots.is_empty());
+        assert!(game.seats.iter().all(|seat| seat.player.shots_fired == 0));
+
+        game.place_figure(player_a, Ship::Submarine, Point::new(0, 0)?)?;
+        game.place_figure(player_b, Ship::Submarine, Point::new(0, 0)?)?;
+
+        // the shots left over from the last game don't cut the first turn short
+        game.shoot(player_a, Point::new(5, 5)?)?;
+        assert_eq!(game.stage, GameStage::PlayerShoots(player_a));
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_resign() -> Result<()> {
+        let mut game = Game::new(GameRules::new());
+
+        let (player_a, _) = game.connect()?;
+        let (player_b, _) = game.connect()?;
+
+        game.resign(player_b)?;
+
+        assert_eq!(game.stage, GameStage::PlayerResigns(player_b));
+        assert_eq!(game.get_state(player_a)?.my_wins, 1);
+        assert!(game.resign(player_a).is_err());
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_expired_offer() -> Result<()> {
+        let mut game = Game::new(GameRules {
+            submarine_limit: 1,
+            destroyer_limit: 0,
+            cruiser_limit: 0,
+            battleship_limit: 0,
+            ..GameRules::new()
+        });
+
+        let (player_a, _) = game.connect()?;
+        let (player_b, _) = game.connect()?;
+
+        game.place_figure(player_a, Ship::Submarine, Point::new(0, 0)?)?;
+        game.place_figure(player_b, Ship::Submarine, Point::new(0, 0)?)?;
+
+        game.make_offer(player_a, Offer::Pause)?;
+        game.shoot(player_a, Point::new(0, 0)?)?;
+
+        assert!(game.accept_offer(player_b, Offer::Pause).is_err());
+        assert_eq!(game.stage, GameStage::PlayerWins(player_a));
+        assert_eq!(game.offer, None);
+        assert!(game.resign(player_b).is_err());
+        assert_eq!(game.series.get_wins(player_a), 1);
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_draw_and_pause() -> Result<()> {
+        let mut game = Game::new(GameRules {
+            submarine_limit: 1,
+            destroyer_limit: 0,
+            cruiser_limit: 0,
+            battleship_limit: 0,
+            ..GameRules::new()
+        });
+
+        let (player_a, _) = game.connect()?;
+        let (player_b, _) = game.connect()?;
+
+        assert!(game.make_offer(player_a, Offer::Draw).is_err());
+
+        game.make_offer(player_a, Offer::Pause)?;
+        game.accept_offer(player_b, Offer::Pause)?;
+
+        assert_eq!(game.stage, GameStage::Paused);
+        assert!(game
+            .place_figure(player_a, Ship::Submarine, Point::new(0, 0)?)
+            .is_err());
+
+        game.make_offer(player_b, Offer::Resume)?;
+        game.accept_offer(player_a, Offer::Resume)?;
+
+        assert_eq!(game.stage, GameStage::PlayerShips(None));
+
+        game.place_figure(player_a, Ship::Submarine, Point::new(0, 0)?)?;
+        game.place_figure(player_b, Ship::Submarine, Point::new(0, 0)?)?;
+
+        game.make_offer(player_b, Offer::Draw)?;
+        game.accept_offer(player_a, Offer::Draw)?;
+
+        assert_eq!(game.stage, GameStage::DrawAgreed);
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_resign_while_paused() -> Result<()> {
+        let mut game = Game::new(GameRules {
+            player_count: 3,
+            submarine_limit: 1,
+            destroyer_limit: 0,
+            cruiser_limit: 0,
+            battleship_limit: 0,
+            ..GameRules::new()
+        });
+
+        let (player_a, _) = game.connect()?;
+        let (player_b, _) = game.connect()?;
+        let (player_c, _) = game.connect()?;
+
+        for player in [player_a, player_b, player_c] {
+            game.place_figure(player, Ship::Submarine, Point::new(0, 0)?)?;
+        }
+
+        game.make_offer(player_b, Offer::Pause)?;
+        game.accept_offer(player_a, Offer::Pause)?;
+        game.accept_offer(player_c, Offer::Pause)?;
+
+        // the turn moves on from the player who gave up, for when the game resumes
+        game.resign(player_a)?;
+
+        assert_eq!(game.stage, GameStage::Paused);
+        assert_eq!(game.paused_stage, Some(GameStage::PlayerShoots(player_b)));
+
+        // the two players left agree among themselves
+        game.make_offer(player_b, Offer::Resume)?;
+        game.accept_offer(player_c, Offer::Resume)?;
+
+        assert_eq!(game.stage, GameStage::PlayerShoots(player_b));
 
         return Ok(());
     }
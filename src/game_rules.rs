@@ -1,20 +1,226 @@
-use crate::{player::Player, ship::Ship};
+use anyhow::{anyhow, Result};
+
+use crate::{
+    fleet,
+    player::Player,
+    point::{BOARD_SIZE, MAX_BOARD_SIZE},
+    ship::{Ship, BATTLESHIP_SIZE, CRUISER_SIZE, DESTROYER_SIZE, SUBMARINE_SIZE},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FirstPlayer {
+    FirstConnected,
+    CoinFlip(u64),
+    PreviousLoser,
+    Alternate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WinCondition {
+    SinkFleets,
+    Survive(u8),
+}
 
 #[derive(Clone, Copy)]
 pub struct GameRules {
+    pub player_count: u8,
+    pub team_size: u8,
+
+    pub board_size: u8,
+
     pub submarine_limit: u8,
     pub destroyer_limit: u8,
     pub cruiser_limit: u8,
     pub battleship_limit: u8,
+
+    pub submarine_armor: u8,
+    pub destroyer_armor: u8,
+    pub cruiser_armor: u8,
+    pub battleship_armor: u8,
+
+    pub repair_limit: u8,
+    pub repair_cooldown: u8,
+
+    pub mobile_fleet: bool,
+
+    pub shot_limit: u8,
+    pub shots_per_turn: u8,
+    pub hint_limit: u8,
+
+    pub simultaneous_turns: bool,
+
+    pub first_player: FirstPlayer,
+
+    pub series_length: u8,
+
+    pub reconnect_window: u16,
+
+    pub spectator_limit: u8,
+    pub reveal_delay: u16,
+
+    pub armada_size: u8,
+
+    pub convoy_rounds: u8,
+    pub sonar_limit: u8,
 }
 
 impl GameRules {
     pub fn new() -> Self {
         return GameRules {
+            player_count: 2,
+            team_size: 1,
+
+            board_size: BOARD_SIZE,
+
             submarine_limit: 4,
             destroyer_limit: 3,
             cruiser_limit: 2,
             battleship_limit: 1,
+
+            submarine_armor: 1,
+            destroyer_armor: 1,
+            cruiser_armor: 1,
+            battleship_armor: 1,
+
+            repair_limit: 0,
+            repair_cooldown: 0,
+
+            mobile_fleet: false,
+
+            shot_limit: 0,
+            shots_per_turn: 1,
+            hint_limit: 0,
+
+            simultaneous_turns: false,
+
+            first_player: FirstPlayer::FirstConnected,
+
+            series_length: 1,
+
+            reconnect_window: 60,
+
+            spectator_limit: 16,
+            reveal_delay: 0,
+
+            armada_size: 0,
+
+            convoy_rounds: 0,
+            sonar_limit: 0,
+        };
+    }
+
+    // players have to split evenly into two teams or more, otherwise some of them would
+    // be left without an enemy, unless they all play together against the armada, whose
+    // oversized fleet has to fit on the board
+    pub fn validate(&self) -> Result<()> {
+        if self.player_count == 0 {
+            return Err(anyhow!("rules need at least one player"));
+        }
+
+        if self.board_size == 0 || self.board_size > MAX_BOARD_SIZE {
+            return Err(anyhow!("board is bound to 1..16 cells a side"));
+        }
+
+        if self.armada_size > 0 {
+            return fleet::check_fit(&self.get_armada_rules())
+                .map_err(|_| anyhow!("armada does not fit on the board"));
+        }
+
+        if self.team_size == 0
+            || !self.player_count.is_multiple_of(self.team_size)
+            || self.player_count / self.team_size < 2
+        {
+            return Err(anyhow!(
+                "{} players cant be split into teams of {}",
+                self.player_count,
+                self.team_size
+            ));
+        }
+
+        return Ok(());
+    }
+
+    // the computer armada takes an extra seat at the foot of the table
+    pub fn get_seat_count(&self) -> usize {
+        return self.player_count as usize + (self.armada_size > 0) as usize;
+    }
+
+    pub fn get_team_count(&self) -> usize {
+        if self.armada_size > 0 {
+            return 2;
+        }
+
+        return (self.player_count / self.team_size.max(1)).max(1) as usize;
+    }
+
+    // allies sit apart, so taking turns around the table alternates between teams,
+    // except against the armada where all players are allies
+    pub fn get_team(&self, seat: usize) -> usize {
+        if self.armada_size > 0 {
+            return (seat >= self.player_count as usize) as usize;
+        }
+
+        return seat % self.get_team_count();
+    }
+
+    // the armada's board grows with its fleet, as far as a point can reach
+    pub fn get_armada_rules(&self) -> GameRules {
+        let area = self.board_size as u16 * self.board_size as u16 * self.armada_size as u16;
+        let board_size = (self.board_size..MAX_BOARD_SIZE)
+            .find(|size| *size as u16 * *size as u16 >= area)
+            .unwrap_or(MAX_BOARD_SIZE);
+
+        return GameRules {
+            board_size,
+            submarine_limit: self.submarine_limit * self.armada_size,
+            destroyer_limit: self.destroyer_limit * self.armada_size,
+            cruiser_limit: self.cruiser_limit * self.armada_size,
+            battleship_limit: self.battleship_limit * self.armada_size,
+            ..*self
+        };
+    }
+
+    // in the convoy scenario the defender in the first seat escorts the regular fleet
+    // and fires freely, while the attacker hunts it with submarines, shots and sonar
+    pub fn get_seat_rules(&self, seat: usize) -> GameRules {
+        if self.armada_size > 0 && seat >= self.player_count as usize {
+            return self.get_armada_rules();
+        }
+
+        if self.convoy_rounds > 0 {
+            return match seat {
+                0 => GameRules {
+                    shot_limit: 0,
+                    sonar_limit: 0,
+                    ..*self
+                },
+                _ => GameRules {
+                    destroyer_limit: 0,
+                    cruiser_limit: 0,
+                    battleship_limit: 0,
+                    ..*self
+                },
+            };
+        }
+
+        return *self;
+    }
+
+    pub fn get_win_condition(&self, seat: usize) -> WinCondition {
+        if self.convoy_rounds > 0 && seat == 0 {
+            return WinCondition::Survive(self.convoy_rounds);
+        }
+
+        return WinCondition::SinkFleets;
+    }
+
+    pub fn get_limit(&self, size: u8) -> u8 {
+        return match size {
+            SUBMARINE_SIZE => self.submarine_limit,
+            DESTROYER_SIZE => self.destroyer_limit,
+            CRUISER_SIZE => self.cruiser_limit,
+            BATTLESHIP_SIZE => self.battleship_limit,
+            _ => 0,
         };
     }
 
@@ -27,6 +233,31 @@ impl GameRules {
         };
     }
 
+    pub fn get_armor(&self, ship: Ship) -> u8 {
+        return match ship {
+            Ship::Submarine => self.submarine_armor,
+            Ship::Destroyer(_) => self.destroyer_armor,
+            Ship::Cruiser(_) => self.cruiser_armor,
+            Ship::Battleship(_) => self.battleship_armor,
+        };
+    }
+
+    pub fn can_repair(&self, player: &Player) -> bool {
+        return player.repairs < self.repair_limit && player.repair_cooldown == 0;
+    }
+
+    pub fn has_available_shots(&self, player: &Player) -> bool {
+        return self.shot_limit == 0 || player.shots_fired < self.shot_limit;
+    }
+
+    pub fn has_available_hints(&self, player: &Player) -> bool {
+        return player.hints_used < self.hint_limit;
+    }
+
+    pub fn has_available_sonar(&self, player: &Player) -> bool {
+        return player.sonar_pings < self.sonar_limit;
+    }
+
     pub fn has_available_ships(&self, player: &Player) -> bool {
         return player.submarines < self.submarine_limit
             || player.destroyers < self.destroyer_limit
@@ -37,7 +268,7 @@ impl GameRules {
 
 #[cfg(test)]
 mod test_game_rules {
-    use crate::game_rules::GameRules;
+    use crate::game_rules::{GameRules, WinCondition};
     use crate::player::Player;
     use crate::point::Orientation;
     use crate::ship::Ship;
@@ -59,7 +290,86 @@ mod test_game_rules {
     }
 
     #[test]
-    pub fn test_has_available_ships(){
+    pub fn test_get_team() {
+        let mut rules = GameRules::new();
+        assert_eq!(rules.get_team(0), 0);
+        assert_eq!(rules.get_team(1), 1);
+
+        rules.player_count = 4;
+        rules.team_size = 2;
+        assert_eq!(rules.get_team_count(), 2);
+        assert_eq!(rules.get_team(2), 0);
+        assert_eq!(rules.get_team(3), 1);
+    }
+
+    #[test]
+    pub fn test_validate() {
+        let mut rules = GameRules::new();
+        assert!(rules.validate().is_ok());
+
+        rules.player_count = 3;
+        rules.team_size = 2;
+        assert!(rules.validate().is_err());
+
+        rules.team_size = 3;
+        assert!(rules.validate().is_err());
+
+        rules.armada_size = 1;
+        assert!(rules.validate().is_ok());
+
+        rules.armada_size = 8;
+        assert!(rules.validate().is_err());
+
+        rules.armada_size = 1;
+        rules.board_size = 17;
+        assert!(rules.validate().is_err());
+    }
+
+    #[test]
+    pub fn test_get_armada_rules() {
+        let mut rules = GameRules::new();
+        rules.armada_size = 2;
+
+        assert_eq!(rules.get_seat_count(), 3);
+        assert_eq!(rules.get_team(1), 0);
+        assert_eq!(rules.get_team(2), 1);
+        assert_eq!(rules.get_armada_rules().submarine_limit, 8);
+        assert_eq!(rules.get_armada_rules().board_size, 15);
+        assert_eq!(rules.get_seat_rules(0).board_size, 10);
+
+        rules.armada_size = 4;
+        assert_eq!(rules.get_armada_rules().board_size, 16);
+    }
+
+    #[test]
+    pub fn test_get_seat_rules() {
+        let mut rules = GameRules::new();
+        rules.shot_limit = 10;
+        rules.sonar_limit = 2;
+        rules.convoy_rounds = 20;
+
+        let defender = rules.get_seat_rules(0);
+        assert_eq!(defender.shot_limit, 0);
+        assert_eq!(defender.sonar_limit, 0);
+        assert_eq!(rules.get_win_condition(0), WinCondition::Survive(20));
+
+        let attacker = rules.get_seat_rules(1);
+        assert_eq!(attacker.shot_limit, 10);
+        assert_eq!(attacker.battleship_limit, 0);
+        assert_eq!(rules.get_win_condition(1), WinCondition::SinkFleets);
+    }
+
+    #[test]
+    pub fn test_get_armor() {
+        let mut rules = GameRules::new();
+        rules.battleship_armor = 2;
+
+        assert_eq!(rules.get_armor(Ship::Submarine), 1);
+        assert_eq!(rules.get_armor(Ship::Battleship(Orientation::Vertical)), 2);
+    }
+
+    #[test]
+    pub fn test_has_available_ships() {
         let rules = GameRules::new();
 
         let mut player = Player::new();
@@ -73,4 +383,34 @@ mod test_game_rules {
         player.submarines = 4;
         assert_eq!(rules.has_available_ships(&player), false);
     }
+
+    #[test]
+    pub fn test_can_repair() {
+        let mut rules = GameRules::new();
+        let mut player = Player::new();
+
+        assert_eq!(rules.can_repair(&player), false);
+
+        rules.repair_limit = 1;
+        assert_eq!(rules.can_repair(&player), true);
+
+        player.repair_cooldown = 1;
+        assert_eq!(rules.can_repair(&player), false);
+
+        player.repair_cooldown = 0;
+        player.repairs = 1;
+        assert_eq!(rules.can_repair(&player), false);
+    }
+
+    #[test]
+    pub fn test_has_available_shots() {
+        let mut rules = GameRules::new();
+        let mut player = Player::new();
+        player.shots_fired = 5;
+
+        assert_eq!(rules.has_available_shots(&player), true);
+
+        rules.shot_limit = 5;
+        assert_eq!(rules.has_available_shots(&player), false);
+    }
 }
@@ -1,18 +1,64 @@
 use anyhow::Result;
 
-type PlayerId = u8;
+use crate::identity::SeatId;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(u8)]
 pub enum GameStage {
     Waiting = 1,
-    PlayerShips(Option<PlayerId>) = 2,
-    PlayerShoots(PlayerId) = 3,
-    PlayerWins(PlayerId) = 4,
+    PlayerShips(Option<SeatId>) = 2,
+    PlayerShoots(SeatId) = 3,
+    PlayerWins(SeatId) = 4,
+    Draw = 5,
+    PlayersShoot(Option<SeatId>) = 6,
+    SeriesWon(SeatId) = 7,
+    PlayerResigns(SeatId) = 8,
+    DrawAgreed = 9,
+    Paused = 10,
+    PlayerAbandons(SeatId) = 11,
 }
 
 impl GameStage {
-    pub fn try_into_u8(&self, my_id: u8) -> Result<u8> {
+    pub fn is_finished(&self) -> bool {
+        return matches!(
+            self,
+            GameStage::PlayerWins(_)
+                | GameStage::Draw
+                | GameStage::SeriesWon(_)
+                | GameStage::PlayerResigns(_)
+                | GameStage::DrawAgreed
+                | GameStage::PlayerAbandons(_)
+        );
+    }
+
+    pub fn is_playing(&self) -> bool {
+        return matches!(
+            self,
+            GameStage::PlayerShips(_) | GameStage::PlayerShoots(_) | GameStage::PlayersShoot(_)
+        );
+    }
+
+    // spectators see the stage as it is, along with the player it's about
+    pub fn get_public_code(&self) -> (u8, Option<SeatId>) {
+        return match *self {
+            GameStage::Waiting => (1, None),
+            GameStage::PlayerShips(player) => (2, player),
+            GameStage::PlayerShoots(player_id) => (3, Some(player_id)),
+            GameStage::PlayerWins(player_id) => (4, Some(player_id)),
+            GameStage::Draw => (5, None),
+            GameStage::PlayersShoot(player) => (6, player),
+            GameStage::SeriesWon(player_id) => (7, Some(player_id)),
+            GameStage::PlayerResigns(player_id) => (8, Some(player_id)),
+            GameStage::DrawAgreed => (9, None),
+            GameStage::Paused => (10, None),
+            GameStage::PlayerAbandons(player_id) => (11, Some(player_id)),
+        };
+    }
+
+    // allies share the outcome of a game, but not each other's turns
+    pub fn try_into_u8(&self, my_id: SeatId, allies: &[SeatId]) -> Result<u8> {
+        let is_my_team = |player_id: &SeatId| *player_id == my_id || allies.contains(player_id);
+
         return match self {
             GameStage::Waiting => Ok(1),
             GameStage::PlayerShips(player) => {
@@ -30,12 +76,43 @@ impl GameStage {
                 }
             }
             GameStage::PlayerWins(player_id) => {
-                if *player_id == my_id {
+                if is_my_team(player_id) {
                     return Ok(6);
                 } else {
                     return Ok(7);
                 }
             }
+            GameStage::Draw => Ok(8),
+            GameStage::PlayersShoot(player) => {
+                if player.is_some() && player.unwrap() != my_id {
+                    return Ok(10);
+                } else {
+                    return Ok(9);
+                }
+            }
+            GameStage::SeriesWon(player_id) => {
+                if is_my_team(player_id) {
+                    return Ok(11);
+                } else {
+                    return Ok(12);
+                }
+            }
+            GameStage::PlayerResigns(player_id) => {
+                if is_my_team(player_id) {
+                    return Ok(13);
+                } else {
+                    return Ok(14);
+                }
+            }
+            GameStage::DrawAgreed => Ok(15),
+            GameStage::Paused => Ok(16),
+            GameStage::PlayerAbandons(player_id) => {
+                if is_my_team(player_id) {
+                    return Ok(17);
+                } else {
+                    return Ok(18);
+                }
+            }
         };
     }
 }
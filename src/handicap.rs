@@ -0,0 +1,70 @@
+use crate::game_rules::GameRules;
+
+// per-seat adjustments on top of the game rules to even out players of different skill
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Handicap {
+    pub submarine_bonus: i8,
+    pub destroyer_bonus: i8,
+    pub cruiser_bonus: i8,
+    pub battleship_bonus: i8,
+
+    pub extra_shots: u8,
+    pub hints: u8,
+}
+
+impl Handicap {
+    pub fn new() -> Self {
+        return Handicap {
+            submarine_bonus: 0,
+            destroyer_bonus: 0,
+            cruiser_bonus: 0,
+            battleship_bonus: 0,
+
+            extra_shots: 0,
+            hints: 0,
+        };
+    }
+
+    pub fn apply(&self, rules: GameRules) -> GameRules {
+        return GameRules {
+            submarine_limit: rules
+                .submarine_limit
+                .saturating_add_signed(self.submarine_bonus),
+            destroyer_limit: rules
+                .destroyer_limit
+                .saturating_add_signed(self.destroyer_bonus),
+            cruiser_limit: rules
+                .cruiser_limit
+                .saturating_add_signed(self.cruiser_bonus),
+            battleship_limit: rules
+                .battleship_limit
+                .saturating_add_signed(self.battleship_bonus),
+            shots_per_turn: rules.shots_per_turn.saturating_add(self.extra_shots),
+            hint_limit: rules.hint_limit.saturating_add(self.hints),
+            ..rules
+        };
+    }
+}
+
+#[cfg(test)]
+mod handicap_test {
+    use super::Handicap;
+    use crate::game_rules::GameRules;
+
+    #[test]
+    pub fn test_apply() {
+        let handicap = Handicap {
+            submarine_bonus: 1,
+            battleship_bonus: -2,
+            extra_shots: 1,
+            ..Handicap::new()
+        };
+
+        let rules = handicap.apply(GameRules::new());
+
+        assert_eq!(rules.submarine_limit, 5);
+        assert_eq!(rules.destroyer_limit, 3);
+        assert_eq!(rules.battleship_limit, 0);
+        assert_eq!(rules.shots_per_turn, 2);
+    }
+}
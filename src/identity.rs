@@ -0,0 +1,49 @@
+use std::{
+    fmt,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+static CONNECTION_COUNT: AtomicU64 = AtomicU64::new(1);
+
+// connections come and go, so they get an opaque id that is never handed out twice
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ConnectionId(u64);
+
+impl ConnectionId {
+    // the computer plays without a connection of its own
+    pub const COMPUTER: ConnectionId = ConnectionId(0);
+
+    pub fn new() -> Self {
+        return ConnectionId(CONNECTION_COUNT.fetch_add(1, Ordering::Relaxed));
+    }
+}
+
+impl fmt::Display for ConnectionId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "#{}", self.0);
+    }
+}
+
+// the game logic only knows seats, which stay put for as long as the game lasts
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SeatId(pub u8);
+
+impl SeatId {
+    pub fn get_index(&self) -> usize {
+        return self.0 as usize;
+    }
+}
+
+#[cfg(test)]
+mod identity_test {
+    use super::ConnectionId;
+
+    #[test]
+    pub fn test_new() {
+        let connection_a = ConnectionId::new();
+        let connection_b = ConnectionId::new();
+
+        assert_ne!(connection_a, connection_b);
+        assert_ne!(connection_a, ConnectionId::COMPUTER);
+    }
+}
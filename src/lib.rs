@@ -1,106 +1,960 @@
+pub mod chat;
+pub mod fleet;
 pub mod game;
 pub mod game_rules;
 pub mod game_stage;
+pub mod handicap;
+pub mod identity;
+pub mod lobby;
+pub mod matchmaking;
+pub mod nickname;
+pub mod offer;
 pub mod player;
 pub mod playmap;
 pub mod point;
+pub mod preset;
+pub mod random;
+pub mod room_registry;
+pub mod series;
 pub mod ship;
 
 use std::{
-    net::TcpListener,
-    sync::{Arc, Mutex},
+    collections::VecDeque,
+    io::ErrorKind,
+    net::{TcpListener, TcpStream},
+    sync::{mpsc::Receiver, Arc, Mutex},
     thread::spawn,
+    time::{Duration, Instant},
 };
 
-use anyhow::Result;
-use game::Game;
+use anyhow::{anyhow, Error, Result};
+use chat::{ChatContent, Emote};
+use game::{Game, PublicView};
 use game_rules::GameRules;
-use point::Point;
+use handicap::Handicap;
+use identity::{ConnectionId, SeatId};
+use lobby::{ChallengeId, Lobby, Notice, PlayerStatus};
+use matchmaking::{Match, Matchmaking, TicketId};
+use nickname::Nickname;
+use offer::Offer;
+use point::{Direction, Point};
+use preset::Preset;
+use room_registry::{Room, RoomId, RoomRegistry};
 use ship::Ship;
-use tungstenite::{accept, Message};
+use tungstenite::{accept, Message, WebSocket};
+
+const MAX_ROOMS: usize = 64;
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+const PROTOCOL_VERSION: u8 = 1;
+
+// server messages other than state updates are tagged above the stage codes
+const ROOM_JOINED: u8 = 100;
+const ERROR: u8 = 101;
+const QUEUE_POSITION: u8 = 102;
+const SESSION: u8 = 103;
+const WELCOME: u8 = 104;
+const CHAT: u8 = 105;
+const EMOTE: u8 = 106;
+const PUBLIC_STATE: u8 = 107;
+const LOBBY: u8 = 108;
+const CHALLENGE: u8 = 109;
+const CHALLENGE_UPDATE: u8 = 110;
+
+const CHALLENGE_PENDING: u8 = 1;
+const CHALLENGE_ACCEPTED: u8 = 2;
+const CHALLENGE_DECLINED: u8 = 3;
+
+// a connection either takes a seat or watches the game from the sidelines
+enum Joined {
+    Player(Match),
+    Spectator(Room),
+}
+
+// state shared by all connections
+struct Server {
+    registry: Mutex<RoomRegistry>,
+    matchmaking: Mutex<Matchmaking>,
+    lobby: Mutex<Lobby>,
+}
 
 pub fn main() {
-    let server = TcpListener::bind("localhost:9001").unwrap();
-    let game = Arc::new(Mutex::new(Game::new(GameRules::new())));
+    let listener = TcpListener::bind("localhost:9001").unwrap();
+    let server = Arc::new(Server {
+        registry: Mutex::new(RoomRegistry::new(MAX_ROOMS)),
+        matchmaking: Mutex::new(Matchmaking::new()),
+        lobby: Mutex::new(Lobby::new()),
+    });
 
-    for stream in server.incoming() {
-        let player_game = game.clone();
+    for stream in listener.incoming() {
+        let server = server.clone();
 
         spawn(move || -> Result<()> {
             let mut websocket = accept(stream.unwrap()).unwrap();
+            websocket.get_ref().set_read_timeout(Some(POLL_INTERVAL))?;
 
-            let mut game_lock = player_game.lock().unwrap();
-            let (my_id, receiver) = game_lock.connect()?;
+            let connection_id = ConnectionId::new();
+            let result = serve(&mut websocket, &server, connection_id);
+            server.lobby.lock().unwrap().sign_out(connection_id);
 
-            drop(game_lock);
+            return result;
+        });
+    }
+}
 
-            loop {
-                if let Ok(_) = receiver.try_recv() {
-                    let mut game_lock = player_game.lock().unwrap();
+fn serve(
+    websocket: &mut WebSocket<TcpStream>,
+    server: &Server,
+    connection_id: ConnectionId,
+) -> Result<()> {
+    let found = match find_room(websocket, server, connection_id)? {
+        Some(Joined::Player(found)) => found,
+        Some(Joined::Spectator(game)) => return spectate(websocket, &game, connection_id),
+        None => return Ok(()),
+    };
 
-                    let stage = game_lock.stage.clone();
-                    let state = game_lock.get_state(my_id)?;
+    // closing the connection leaves the game, while a dropped one may come back
+    let result = play(websocket, &found);
 
-                    let mut payload: Vec<u8> = Vec::new();
-                    payload.push(stage.try_into_u8(my_id)?);
-                    payload.extend(state.my_ships.value.to_be_bytes());
-                    payload.extend(state.my_marks.value.to_be_bytes());
-                    payload.extend(state.enemy_marks.value.to_be_bytes());
-                    payload.extend(state.enemy_losses.value.to_be_bytes());
+    let mut game_lock = found.game.lock().unwrap();
+    match result {
+        Ok(()) => game_lock.leave(found.player_id)?,
+        Err(_) => game_lock.disconnect(found.player_id)?,
+    };
+    game_lock.trigger_sync()?;
+    drop(game_lock);
 
-                    websocket.send(Message::Binary(payload))?;
+    server.registry.lock().unwrap().cleanup();
 
-                    drop(game_lock);
+    return result;
+}
+
+// players first introduce themselves, then browse the lobby, create a room, join
+// an existing one, queue up for a match or challenge another player
+fn find_room(
+    websocket: &mut WebSocket<TcpStream>,
+    server: &Server,
+    connection_id: ConnectionId,
+) -> Result<Option<Joined>> {
+    let mut nickname: Option<Nickname> = None;
+    let mut notices: Option<Receiver<Notice>> = None;
+    let mut ticket: Option<(TicketId, Receiver<Match>)> = None;
+    let mut last_position = None;
+    let mut is_browsing = false;
+    let mut last_lobby: Option<Vec<u8>> = None;
+
+    loop {
+        if let Some((ticket_id, matches)) = &ticket {
+            if let Ok(found) = matches.try_recv() {
+                return Ok(Some(enter_room(websocket, server, connection_id, found)?));
+            }
+
+            let matchmaking_lock = server.matchmaking.lock().unwrap();
+            let position = (
+                matchmaking_lock.get_position(*ticket_id).unwrap_or(0) as u16,
+                matchmaking_lock.get_estimated_wait().as_secs() as u16,
+            );
+            drop(matchmaking_lock);
+
+            if last_position != Some(position) {
+                let mut payload = vec![QUEUE_POSITION];
+                payload.extend(position.0.to_be_bytes());
+                payload.extend(position.1.to_be_bytes());
+                websocket.send(Message::Binary(payload))?;
+
+                last_position = Some(position);
+            }
+        }
+
+        // challenges reach the players involved through the lobby
+        let received: Vec<Notice> = notices
+            .iter()
+            .flat_map(|notices| notices.try_iter())
+            .collect();
+        for notice in received {
+            match notice {
+                Notice::Challenged {
+                    challenge_id,
+                    nickname: challenger,
+                    preset,
+                } => {
+                    let mut payload = vec![CHALLENGE];
+                    payload.extend(challenge_id.to_be_bytes());
+                    payload.push(preset as u8);
+                    payload.extend(challenger.as_str().as_bytes());
+                    websocket.send(Message::Binary(payload))?;
                 }
+                Notice::Declined { challenge_id } => {
+                    send_challenge_update(websocket, challenge_id, CHALLENGE_DECLINED)?;
+                }
+                Notice::Accepted {
+                    challenge_id,
+                    room_id,
+                    code,
+                } => {
+                    if let Some((ticket_id, _)) = ticket.take() {
+                        server.matchmaking.lock().unwrap().leave(ticket_id);
+                    }
 
-                let msg = websocket.read().unwrap();
-
-                match msg {
-                    Message::Binary(bin) => {
-                        match bin[..] {
-                            // player places a ship
-                            [1, ship_u8, point_u8] => {
-                                let mut game_lock = player_game.lock().unwrap();
-
-                                game_lock.place_figure(
-                                    my_id,
-                                    Ship::try_from(ship_u8)?,
-                                    Point::try_from(point_u8)?,
-                                )?;
-
-                                game_lock.trigger_sync()?;
-                                drop(game_lock);
-                            }
-                            // player removes a ship
-                            [2, point_u8] => {
-                                let mut game_lock = player_game.lock().unwrap();
-
-                                game_lock.remove_figure(my_id, Point::try_from(point_u8)?)?;
-
-                                game_lock.trigger_sync()?;
-                                drop(game_lock);
-                            }
-                            // player shoots at point
-                            [3, point_u8] => {
-                                let mut game_lock = player_game.lock().unwrap();
-
-                                game_lock.shoot(my_id, Point::try_from(point_u8)?)?;
-
-                                game_lock.trigger_sync()?;
-                                drop(game_lock);
-                            }
-                            _ => {}
+                    send_challenge_update(websocket, challenge_id, CHALLENGE_ACCEPTED)?;
+                    let nickname = nickname.as_ref().unwrap();
+                    match join_challenge(server, connection_id, nickname, room_id, code) {
+                        Ok(found) => {
+                            return Ok(Some(enter_room(websocket, server, connection_id, found)?));
                         }
+                        Err(error) => send_error(websocket, error)?,
                     }
-                    Message::Close(_) => {
-                        let mut game_lock = player_game.lock().unwrap();
-                        game_lock.disconnect(my_id)?;
+                }
+            }
+        }
+
+        // the lobby is sent again whenever anything in it changes
+        if is_browsing {
+            let payload = get_lobby_payload(server);
+            if last_lobby.as_ref() != Some(&payload) {
+                websocket.send(Message::Binary(payload.clone()))?;
+                last_lobby = Some(payload);
+            }
+        }
 
-                        drop(game_lock);
+        let bin = match read_message(websocket) {
+            Ok(Some(Message::Binary(bin))) => bin,
+            Ok(Some(Message::Close(_))) | Err(_) => {
+                if let Some((ticket_id, matches)) = ticket {
+                    leave_queue(server, ticket_id, &matches)?;
+                }
+                return Ok(None);
+            }
+            _ => continue,
+        };
+
+        match bin[..] {
+            // player says hello with the client version and a nickname
+            [19, version, ref name @ ..] if ticket.is_none() => {
+                match greet(server, connection_id, version, name) {
+                    Ok((greeted, greeted_notices)) => {
+                        nickname = Some(greeted);
+                        notices = Some(greeted_notices);
+                        websocket.send(Message::Binary(vec![WELCOME, PROTOCOL_VERSION]))?;
                     }
-                    _ => {}
+                    Err(error) => send_error(websocket, error)?,
                 }
             }
-        });
+            _ if nickname.is_none() => {
+                send_error(websocket, anyhow!("say hello first"))?;
+            }
+            // player starts browsing the lobby
+            [29] => is_browsing = true,
+            // player stops browsing the lobby
+            [30] => {
+                is_browsing = false;
+                last_lobby = None;
+            }
+            // player queues up for a match, optionally with a preferred preset
+            [22, preset_u8] if ticket.is_none() => {
+                let nickname = nickname.clone().unwrap();
+                match join_queue(server, preset_u8, connection_id, nickname) {
+                    Ok(queued) => ticket = Some(queued),
+                    Err(error) => send_error(websocket, error)?,
+                }
+            }
+            // player leaves the queue
+            [23] => {
+                if let Some((ticket_id, matches)) = ticket.take() {
+                    leave_queue(server, ticket_id, &matches)?;
+                    server.lobby.lock().unwrap().set_status(
+                        connection_id,
+                        PlayerStatus::Idle,
+                        None,
+                    );
+                    last_position = None;
+                }
+            }
+            // player declines a challenge
+            [33, a, b, c, d] => {
+                let challenge_id = ChallengeId::from_be_bytes([a, b, c, d]);
+                if let Err(error) = server
+                    .lobby
+                    .lock()
+                    .unwrap()
+                    .decline(connection_id, challenge_id)
+                {
+                    send_error(websocket, error)?;
+                }
+            }
+            _ if ticket.is_some() => {
+                send_error(websocket, anyhow!("leave the queue first"))?;
+            }
+            // player challenges an online player by their nickname to a game with a rules preset
+            [31, preset_u8, ref name @ ..] => {
+                match challenge_player(server, connection_id, preset_u8, name) {
+                    Ok(challenge_id) => {
+                        send_challenge_update(websocket, challenge_id, CHALLENGE_PENDING)?;
+                    }
+                    Err(error) => send_error(websocket, error)?,
+                }
+            }
+            // player accepts a challenge, which seats both players in a new private room
+            [32, a, b, c, d] => {
+                let challenge_id = ChallengeId::from_be_bytes([a, b, c, d]);
+                let nickname = nickname.as_ref().unwrap();
+                match accept_challenge(server, connection_id, nickname, challenge_id) {
+                    Ok(found) => {
+                        return Ok(Some(enter_room(websocket, server, connection_id, found)?));
+                    }
+                    Err(error) => send_error(websocket, error)?,
+                }
+            }
+            [27, ..] | [28, ..] => match watch_room(&server.registry, &bin) {
+                Ok((room_id, game)) => {
+                    server.lobby.lock().unwrap().set_status(
+                        connection_id,
+                        PlayerStatus::Watching,
+                        Some(room_id),
+                    );
+                    send_room_joined(websocket, room_id, None)?;
+                    return Ok(Some(Joined::Spectator(game)));
+                }
+                Err(error) => send_error(websocket, error)?,
+            },
+            _ => {
+                let nickname = nickname.as_ref().unwrap();
+                match join_room(&server.registry, &bin, connection_id, nickname) {
+                    Ok(found) => {
+                        return Ok(Some(enter_room(websocket, server, connection_id, found)?));
+                    }
+                    Err(error) => send_error(websocket, error)?,
+                }
+            }
+        }
+    }
+}
+
+fn enter_room(
+    websocket: &mut WebSocket<TcpStream>,
+    server: &Server,
+    connection_id: ConnectionId,
+    found: Match,
+) -> Result<Joined> {
+    server.lobby.lock().unwrap().set_status(
+        connection_id,
+        PlayerStatus::Playing,
+        Some(found.room_id),
+    );
+    if let Err(error) = send_room_joined(websocket, found.room_id, found.code.as_deref()) {
+        give_up_match(server, found)?;
+        return Err(error);
+    }
+
+    return Ok(Joined::Player(found));
+}
+
+// a match may have come in just before the player left the queue
+fn leave_queue(server: &Server, ticket_id: TicketId, matches: &Receiver<Match>) -> Result<()> {
+    server.matchmaking.lock().unwrap().leave(ticket_id);
+
+    for found in matches.try_iter() {
+        give_up_match(server, found)?;
+    }
+
+    return Ok(());
+}
+
+// nobody is going to play the seat, so it's left straight away
+fn give_up_match(server: &Server, found: Match) -> Result<()> {
+    let mut game_lock = found.game.lock().unwrap();
+    game_lock.leave(found.player_id)?;
+    game_lock.trigger_sync()?;
+    drop(game_lock);
+
+    server.registry.lock().unwrap().cleanup();
+
+    return Ok(());
+}
+
+// every room comes with a summary of its rules: player count, team size, armada size,
+// series length, shot limit, convoy rounds and flags for simultaneous turns, mobile
+// fleets, repairs and broadcasting
+fn get_lobby_payload(server: &Server) -> Vec<u8> {
+    let rooms = server.registry.lock().unwrap().get_open_rooms();
+
+    let mut payload = vec![LOBBY, rooms.len() as u8];
+    for room in rooms {
+        let rules = room.rules;
+        let flags = rules.simultaneous_turns as u8
+            | (rules.mobile_fleet as u8) << 1
+            | ((rules.repair_limit > 0) as u8) << 2
+            | ((rules.reveal_delay > 0) as u8) << 3;
+
+        payload.extend(room.room_id.to_be_bytes());
+        payload.push(room.stage.get_public_code().0);
+        payload.extend([
+            rules.player_count,
+            rules.team_size,
+            rules.armada_size,
+            rules.series_length,
+            rules.shot_limit,
+            rules.convoy_rounds,
+            flags,
+        ]);
+        payload.push(room.spectator_count as u8);
+        payload.push(room.nicknames.len() as u8);
+        for nickname in room.nicknames {
+            push_nickname(&mut payload, nickname);
+        }
+    }
+
+    let lobby = server.lobby.lock().unwrap();
+    let players = lobby.get_players();
+
+    payload.extend((players.len() as u16).to_be_bytes());
+    for player in players {
+        payload.push(player.status as u8);
+        payload.extend(player.room_id.unwrap_or(0).to_be_bytes());
+        push_nickname(&mut payload, Some(player.nickname.clone()));
+    }
+
+    return payload;
+}
+
+fn play(websocket: &mut WebSocket<TcpStream>, found: &Match) -> Result<()> {
+    let my_id = found.player_id;
+    let mut chat_cursor = 0;
+
+    let game_lock = found.game.lock().unwrap();
+    let mut payload = vec![SESSION];
+    payload.extend(game_lock.get_session_token(my_id)?.to_be_bytes());
+    websocket.send(Message::Binary(payload))?;
+
+    game_lock.trigger_sync()?;
+    drop(game_lock);
+
+    loop {
+        let mut game_lock = found.game.lock().unwrap();
+        if game_lock.expire_sessions()? {
+            game_lock.trigger_sync()?;
+        }
+        drop(game_lock);
+
+        // the first update also replays the chat history to a joining player
+        if found.receiver.try_iter().count() > 0 {
+            let mut game_lock = found.game.lock().unwrap();
+            send_state(websocket, &mut game_lock, my_id)?;
+            chat_cursor = send_chat(websocket, &game_lock, chat_cursor)?;
+        }
+
+        let bin = match read_message(websocket)? {
+            Some(Message::Binary(bin)) => bin,
+            Some(Message::Close(_)) => return Ok(()),
+            _ => continue,
+        };
+
+        let mut game_lock = found.game.lock().unwrap();
+        match handle_command(&mut game_lock, my_id, &bin) {
+            Ok(()) => game_lock.trigger_sync()?,
+            Err(error) => send_error(websocket, error)?,
+        }
     }
 }
+
+fn spectate(
+    websocket: &mut WebSocket<TcpStream>,
+    game: &Room,
+    connection_id: ConnectionId,
+) -> Result<()> {
+    let receiver = game.lock().unwrap().spectate(connection_id)?;
+    let result = watch(websocket, game, &receiver);
+    game.lock().unwrap().stop_spectating(connection_id);
+
+    return result;
+}
+
+// in broadcast mode the fleets are revealed, but every update is held back by the delay
+// so that players can't peek at each other's ships on the stream
+fn watch(websocket: &mut WebSocket<TcpStream>, game: &Room, receiver: &Receiver<()>) -> Result<()> {
+    let delay = Duration::from_secs(game.lock().unwrap().rules.reveal_delay as u64);
+    let mut delayed: VecDeque<(Instant, Vec<u8>)> = VecDeque::new();
+
+    loop {
+        if receiver.try_iter().count() > 0 {
+            let view = game.lock().unwrap().get_public_view(!delay.is_zero())?;
+            delayed.push_back((Instant::now(), get_public_payload(view)));
+        }
+
+        while delayed
+            .front()
+            .is_some_and(|(changed_at, _)| changed_at.elapsed() >= delay)
+        {
+            let (_, payload) = delayed.pop_front().unwrap();
+            websocket.send(Message::Binary(payload))?;
+        }
+
+        if let Some(Message::Close(_)) = read_message(websocket)? {
+            return Ok(());
+        }
+    }
+}
+
+fn get_public_payload(view: PublicView) -> Vec<u8> {
+    let (stage_u8, stage_id) = view.stage.get_public_code();
+
+    let mut payload = vec![PUBLIC_STATE, stage_u8];
+    payload.push(stage_id.map_or(0, |seat| seat.0 + 1));
+    payload.push(view.turn_seat.map_or(0, |seat| seat as u8 + 1));
+    payload.push(view.best_of);
+    payload.push(view.round);
+    payload.push(view.players.len() as u8);
+    for player in view.players {
+        payload.push(player.seat as u8);
+        payload.push(player.is_alive as u8);
+        payload.push(player.wins);
+        payload.push(player.ships.board_size);
+        payload.extend(player.ships.to_be_bytes());
+        payload.extend(player.marks.to_be_bytes());
+        payload.extend(player.losses.to_be_bytes());
+        payload.extend(player.damage.to_be_bytes());
+        push_nickname(&mut payload, player.nickname);
+    }
+
+    return payload;
+}
+
+fn send_state(websocket: &mut WebSocket<TcpStream>, game: &mut Game, my_id: SeatId) -> Result<()> {
+    let stage = game.stage.clone();
+    let allies = game.get_allies(my_id)?;
+    let state = game.get_state(my_id)?;
+
+    let mut payload: Vec<u8> = Vec::new();
+    payload.push(stage.try_into_u8(my_id, &allies)?);
+    payload.push(state.my_seat as u8);
+    payload.push(state.turn_seat.map_or(0, |seat| seat as u8 + 1));
+    payload.extend(state.my_ships.to_be_bytes());
+    payload.extend(state.my_marks.to_be_bytes());
+    payload.extend(state.my_damage.to_be_bytes());
+    payload.push(match state.first_player {
+        Some(player_id) if player_id == my_id => 1,
+        Some(_) => 2,
+        None => 0,
+    });
+    payload.push(state.best_of);
+    payload.push(state.my_wins);
+    payload.push(state.round);
+    match state.offer {
+        Some((player_id, offer)) if player_id == my_id => payload.extend([offer as u8, 1]),
+        Some((_, offer)) => payload.extend([offer as u8, 2]),
+        None => payload.extend([0, 0]),
+    };
+    payload.push(state.allies.len() as u8);
+    for ally in state.allies {
+        payload.push(ally.seat as u8);
+        payload.push(ally.is_alive as u8);
+        payload.extend(ally.ships.to_be_bytes());
+        payload.extend(ally.marks.to_be_bytes());
+        payload.extend(ally.damage.to_be_bytes());
+        push_nickname(&mut payload, ally.nickname);
+    }
+    payload.push(state.enemies.len() as u8);
+    for enemy in state.enemies {
+        payload.push(enemy.seat as u8);
+        payload.push(enemy.is_alive as u8);
+        payload.push(enemy.wins);
+        payload.push(enemy.marks.board_size);
+        payload.extend(enemy.marks.to_be_bytes());
+        payload.extend(enemy.losses.to_be_bytes());
+        payload.extend(enemy.damage.to_be_bytes());
+        payload.extend(enemy.pings.to_be_bytes());
+        push_nickname(&mut payload, enemy.nickname);
+    }
+
+    websocket.send(Message::Binary(payload))?;
+
+    return Ok(());
+}
+
+// nicknames are prefixed with their length, empty for the computer and for seats nobody took yet
+fn push_nickname(payload: &mut Vec<u8>, nickname: Option<Nickname>) {
+    let name = nickname.map_or(String::new(), |nickname| nickname.to_string());
+    payload.push(name.len() as u8);
+    payload.extend(name.into_bytes());
+}
+
+fn send_chat(websocket: &mut WebSocket<TcpStream>, game: &Game, chat_cursor: u32) -> Result<u32> {
+    let mut last_id = chat_cursor;
+
+    for message in game.get_chat_since(chat_cursor) {
+        let payload = match message.content {
+            ChatContent::Text(text) => [vec![CHAT, message.seat.0], text.into_bytes()].concat(),
+            ChatContent::Emote(emote) => vec![EMOTE, message.seat.0, emote as u8],
+        };
+        websocket.send(Message::Binary(payload))?;
+
+        last_id = message.message_id;
+    }
+
+    return Ok(last_id);
+}
+
+fn handle_command(game: &mut Game, my_id: SeatId, bin: &[u8]) -> Result<()> {
+    match *bin {
+        // player places a ship
+        [1, ship_u8, point_u8] => {
+            game.place_figure(my_id, Ship::try_from(ship_u8)?, Point::try_from(point_u8)?)?
+        }
+        // player removes a ship
+        [2, point_u8] => game.remove_figure(my_id, Point::try_from(point_u8)?)?,
+        // player shoots at point
+        [3, point_u8] => game.shoot(my_id, Point::try_from(point_u8)?)?,
+        // player shoots at point on the board of the player in seat
+        [3, point_u8, seat_u8] => {
+            game.shoot_at(my_id, seat_u8 as usize, Point::try_from(point_u8)?)?
+        }
+        // player repairs a damaged ship at point
+        [4, point_u8] => game.repair(my_id, Point::try_from(point_u8)?)?,
+        // player moves a ship at point by one cell
+        [5, point_u8, direction_u8] => game.move_figure(
+            my_id,
+            Point::try_from(point_u8)?,
+            Direction::try_from(direction_u8)?,
+        )?,
+        // player makes an offer to the enemy
+        [6, offer_u8] => game.make_offer(my_id, Offer::try_from(offer_u8)?)?,
+        // player accepts the enemy's offer
+        [7, offer_u8] => game.accept_offer(my_id, Offer::try_from(offer_u8)?)?,
+        // player resigns
+        [8] => game.resign(my_id)?,
+        // player pings the area around point with sonar
+        [9, point_u8] => game.sonar(my_id, Point::try_from(point_u8)?)?,
+        // player pings the area around point on the board of the player in seat
+        [9, point_u8, seat_u8] => {
+            game.sonar_at(my_id, seat_u8 as usize, Point::try_from(point_u8)?)?
+        }
+        // player asks for a hint on the enemy's fleet
+        [10] => game.hint(my_id)?,
+        // player asks for a hint on the fleet of the player in seat
+        [10, seat_u8] => game.hint_at(my_id, seat_u8 as usize)?,
+        // player posts a chat message
+        [11, ref text @ ..] => game.post_chat(my_id, ChatContent::parse_text(text)?)?,
+        // player sends a quick emote
+        [12, emote_u8] => game.post_chat(my_id, ChatContent::Emote(Emote::try_from(emote_u8)?))?,
+        // host evens out the player in seat with a handicap
+        [13, seat_u8, submarine_u8, destroyer_u8, cruiser_u8, battleship_u8, shots_u8, hints_u8] => {
+            let handicap = Handicap {
+                submarine_bonus: submarine_u8 as i8,
+                destroyer_bonus: destroyer_u8 as i8,
+                cruiser_bonus: cruiser_u8 as i8,
+                battleship_bonus: battleship_u8 as i8,
+                extra_shots: shots_u8,
+                hints: hints_u8,
+            };
+            game.set_handicap(my_id, seat_u8 as usize, handicap)?
+        }
+        _ => {}
+    };
+
+    return Ok(());
+}
+
+fn greet(
+    server: &Server,
+    connection_id: ConnectionId,
+    version: u8,
+    name: &[u8],
+) -> Result<(Nickname, Receiver<Notice>)> {
+    if version == 0 || version > PROTOCOL_VERSION {
+        return Err(anyhow!(
+            "cant greet, unsupported client version {}",
+            version
+        ));
+    }
+
+    let nickname = Nickname::try_from(name)?;
+    let notices = server
+        .lobby
+        .lock()
+        .unwrap()
+        .sign_in(connection_id, nickname.clone())?;
+
+    return Ok((nickname, notices));
+}
+
+fn challenge_player(
+    server: &Server,
+    connection_id: ConnectionId,
+    preset_u8: u8,
+    name: &[u8],
+) -> Result<ChallengeId> {
+    let preset = Preset::try_from(preset_u8)?;
+    let nickname = Nickname::try_from(name)?;
+
+    return server
+        .lobby
+        .lock()
+        .unwrap()
+        .challenge(connection_id, &nickname, preset);
+}
+
+// the challenger follows into the room once they hear that the challenge was accepted
+fn accept_challenge(
+    server: &Server,
+    connection_id: ConnectionId,
+    nickname: &Nickname,
+    challenge_id: ChallengeId,
+) -> Result<Match> {
+    let challenge = server
+        .lobby
+        .lock()
+        .unwrap()
+        .accept(connection_id, challenge_id)?;
+
+    // the room is taken before a cleanup elsewhere can see it empty
+    let mut registry = server.registry.lock().unwrap();
+    let (room_id, code, game) = registry.create_private_room(challenge.preset.get_rules(), None)?;
+    let connected = game
+        .lock()
+        .unwrap()
+        .connect_as(connection_id, nickname.clone());
+    let (player_id, receiver) = match connected {
+        Ok(connected) => connected,
+        Err(error) => {
+            registry.cleanup();
+            return Err(error);
+        }
+    };
+    drop(registry);
+
+    let accepted = Notice::Accepted {
+        challenge_id,
+        room_id,
+        code: code.clone(),
+    };
+    if let Err(error) = server
+        .lobby
+        .lock()
+        .unwrap()
+        .notify(challenge.challenger, accepted)
+    {
+        game.lock().unwrap().disconnect(player_id)?;
+        server.registry.lock().unwrap().cleanup();
+        return Err(error);
+    }
+
+    return Ok(Match {
+        room_id,
+        code: Some(code),
+        game,
+        player_id,
+        receiver,
+    });
+}
+
+fn join_challenge(
+    server: &Server,
+    connection_id: ConnectionId,
+    nickname: &Nickname,
+    room_id: RoomId,
+    code: String,
+) -> Result<Match> {
+    let (_, game) = server
+        .registry
+        .lock()
+        .unwrap()
+        .get_private_room(&code, None)?;
+    let (player_id, receiver) = game
+        .lock()
+        .unwrap()
+        .connect_as(connection_id, nickname.clone())?;
+
+    return Ok(Match {
+        room_id,
+        code: Some(code),
+        game,
+        player_id,
+        receiver,
+    });
+}
+
+fn join_room(
+    registry: &Mutex<RoomRegistry>,
+    bin: &[u8],
+    connection_id: ConnectionId,
+    nickname: &Nickname,
+) -> Result<Match> {
+    let mut registry = registry.lock().unwrap();
+
+    let (room_id, code, game) = match *bin {
+        // player creates a room with a rules preset
+        [20, preset_u8] => {
+            let (room_id, game) = registry.create_room(Preset::try_from(preset_u8)?.get_rules())?;
+            (room_id, None, game)
+        }
+        // player creates a room with a rules preset, broadcast to spectators with a delay
+        [20, preset_u8, a, b] => {
+            let rules = GameRules {
+                reveal_delay: u16::from_be_bytes([a, b]),
+                ..Preset::try_from(preset_u8)?.get_rules()
+            };
+            let (room_id, game) = registry.create_room(rules)?;
+            (room_id, None, game)
+        }
+        // player joins a room by its id
+        [21, a, b, c, d] => {
+            let room_id = RoomId::from_be_bytes([a, b, c, d]);
+            (room_id, None, registry.get_room(room_id)?)
+        }
+        // player creates a private room, optionally locked with a password
+        [24, preset_u8, ref password @ ..] => {
+            let (room_id, code, game) = registry.create_private_room(
+                Preset::try_from(preset_u8)?.get_rules(),
+                parse_password(password)?,
+            )?;
+            (room_id, Some(code), game)
+        }
+        // player returns to a room with the session token of a dropped connection
+        [26, a, b, c, d, ref token @ ..] if token.len() == 8 => {
+            let room_id = RoomId::from_be_bytes([a, b, c, d]);
+            let (game, player_id, receiver) = registry.rejoin_room(
+                room_id,
+                connection_id,
+                u64::from_be_bytes(token.try_into()?),
+            )?;
+
+            return Ok(Match {
+                room_id,
+                code: None,
+                game,
+                player_id,
+                receiver,
+            });
+        }
+        // player joins a private room with its code and password
+        [25, a, b, c, d, e, f, ref password @ ..] => {
+            let code = String::from_utf8(vec![a, b, c, d, e, f])?;
+            let password = parse_password(password)?;
+            let (room_id, game) = registry.get_private_room(&code, password.as_deref())?;
+            (room_id, Some(code.to_uppercase()), game)
+        }
+        _ => return Err(anyhow!("create or join a room first")),
+    };
+
+    let (player_id, receiver) = game
+        .lock()
+        .unwrap()
+        .connect_as(connection_id, nickname.clone())?;
+
+    return Ok(Match {
+        room_id,
+        code,
+        game,
+        player_id,
+        receiver,
+    });
+}
+
+fn watch_room(registry: &Mutex<RoomRegistry>, bin: &[u8]) -> Result<(RoomId, Room)> {
+    let registry = registry.lock().unwrap();
+
+    return match *bin {
+        // spectator watches a room by its id
+        [27, a, b, c, d] => {
+            let room_id = RoomId::from_be_bytes([a, b, c, d]);
+            Ok((room_id, registry.get_room(room_id)?))
+        }
+        // spectator watches a private room with its code and password
+        [28, a, b, c, d, e, f, ref password @ ..] => {
+            let code = String::from_utf8(vec![a, b, c, d, e, f])?;
+            let password = parse_password(password)?;
+            registry.get_private_room(&code, password.as_deref())
+        }
+        _ => Err(anyhow!("unknown room")),
+    };
+}
+
+fn parse_password(bin: &[u8]) -> Result<Option<String>> {
+    if bin.is_empty() {
+        return Ok(None);
+    }
+
+    return Ok(Some(String::from_utf8(bin.to_vec())?));
+}
+
+fn join_queue(
+    server: &Server,
+    preset_u8: u8,
+    connection_id: ConnectionId,
+    nickname: Nickname,
+) -> Result<(TicketId, Receiver<Match>)> {
+    let preset = match preset_u8 {
+        0 => None,
+        _ => Some(Preset::try_from(preset_u8)?),
+    };
+
+    let mut matchmaking = server.matchmaking.lock().unwrap();
+    let queued = matchmaking.enqueue(preset, connection_id, nickname);
+
+    // a player whose match couldn't be set up stays out of the queue, unless they
+    // were matched before another match failed
+    let paired = matchmaking.pair_players(&mut server.registry.lock().unwrap());
+    if let Err(error) = paired {
+        if matchmaking.get_position(queued.0).is_some() {
+            matchmaking.leave(queued.0);
+            return Err(error);
+        }
+    }
+    drop(matchmaking);
+
+    server
+        .lobby
+        .lock()
+        .unwrap()
+        .set_status(connection_id, PlayerStatus::Queued, None);
+
+    return Ok(queued);
+}
+
+// reads time out, so that connections get to pick up updates in between messages
+fn read_message(websocket: &mut WebSocket<TcpStream>) -> Result<Option<Message>> {
+    return match websocket.read() {
+        Ok(msg) => Ok(Some(msg)),
+        Err(tungstenite::Error::Io(error))
+            if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+        {
+            Ok(None)
+        }
+        Err(error) => Err(error.into()),
+    };
+}
+
+fn send_room_joined(
+    websocket: &mut WebSocket<TcpStream>,
+    room_id: RoomId,
+    code: Option<&str>,
+) -> Result<()> {
+    let mut payload = vec![ROOM_JOINED];
+    payload.extend(room_id.to_be_bytes());
+    if let Some(code) = code {
+        payload.extend(code.as_bytes());
+    }
+    websocket.send(Message::Binary(payload))?;
+
+    return Ok(());
+}
+
+fn send_challenge_update(
+    websocket: &mut WebSocket<TcpStream>,
+    challenge_id: ChallengeId,
+    status: u8,
+) -> Result<()> {
+    let mut payload = vec![CHALLENGE_UPDATE];
+    payload.extend(challenge_id.to_be_bytes());
+    payload.push(status);
+    websocket.send(Message::Binary(payload))?;
+
+    return Ok(());
+}
+
+fn send_error(websocket: &mut WebSocket<TcpStream>, error: Error) -> Result<()> {
+    let mut payload = vec![ERROR];
+    payload.extend(error.to_string().into_bytes());
+    websocket.send(Message::Binary(payload))?;
+
+    return Ok(());
+}
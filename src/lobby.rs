@@ -0,0 +1,307 @@
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use anyhow::{anyhow, Result};
+
+use crate::{identity::ConnectionId, nickname::Nickname, preset::Preset, room_registry::RoomId};
+
+pub type ChallengeId = u32;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(u8)]
+pub enum PlayerStatus {
+    Idle = 1,
+    Queued = 2,
+    Playing = 3,
+    Watching = 4,
+}
+
+#[derive(Clone, Debug)]
+pub struct OnlinePlayer {
+    pub connection_id: ConnectionId,
+    pub nickname: Nickname,
+    pub status: PlayerStatus,
+    pub room_id: Option<RoomId>,
+    sender: Sender<Notice>,
+}
+
+// messages routed from one connection to another through the lobby
+#[derive(Debug)]
+pub enum Notice {
+    Challenged {
+        challenge_id: ChallengeId,
+        nickname: Nickname,
+        preset: Preset,
+    },
+    Declined {
+        challenge_id: ChallengeId,
+    },
+    Accepted {
+        challenge_id: ChallengeId,
+        room_id: RoomId,
+        code: String,
+    },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Challenge {
+    pub challenge_id: ChallengeId,
+    pub challenger: ConnectionId,
+    pub target: ConnectionId,
+    pub preset: Preset,
+}
+
+// everyone who said hello is online until the connection closes, under a name nobody
+// else on the server goes by
+pub struct Lobby {
+    players: Vec<OnlinePlayer>,
+    challenges: Vec<Challenge>,
+    challenge_count: ChallengeId,
+}
+
+impl Lobby {
+    pub fn new() -> Self {
+        return Lobby {
+            players: Vec::new(),
+            challenges: Vec::new(),
+            challenge_count: 0,
+        };
+    }
+
+    pub fn sign_in(
+        &mut self,
+        connection_id: ConnectionId,
+        nickname: Nickname,
+    ) -> Result<Receiver<Notice>> {
+        let is_taken = self
+            .players
+            .iter()
+            .any(|player| player.connection_id != connection_id && player.nickname == nickname);
+        if is_taken {
+            return Err(anyhow!("cant greet, nickname {} is taken", nickname));
+        }
+
+        let (sender, receiver) = channel();
+
+        self.sign_out(connection_id);
+        self.players.push(OnlinePlayer {
+            connection_id,
+            nickname,
+            status: PlayerStatus::Idle,
+            room_id: None,
+            sender,
+        });
+
+        return Ok(receiver);
+    }
+
+    // challenges from and to a player go away with them
+    pub fn sign_out(&mut self, connection_id: ConnectionId) {
+        self.players
+            .retain(|player| player.connection_id != connection_id);
+        self.challenges.retain(|challenge| {
+            challenge.challenger != connection_id && challenge.target != connection_id
+        });
+    }
+
+    pub fn set_status(
+        &mut self,
+        connection_id: ConnectionId,
+        status: PlayerStatus,
+        room_id: Option<RoomId>,
+    ) {
+        if let Some(player) = self
+            .players
+            .iter_mut()
+            .find(|player| player.connection_id == connection_id)
+        {
+            player.status = status;
+            player.room_id = room_id;
+        }
+    }
+
+    pub fn get_players(&self) -> &[OnlinePlayer] {
+        return &self.players;
+    }
+
+    // only players who are idle in the lobby can challenge each other
+    pub fn challenge(
+        &mut self,
+        connection_id: ConnectionId,
+        nickname: &Nickname,
+        preset: Preset,
+    ) -> Result<ChallengeId> {
+        let challenger = self.get_player(connection_id)?;
+        if challenger.status != PlayerStatus::Idle {
+            return Err(anyhow!("cant challenge, you are busy"));
+        }
+        let challenger_nickname = challenger.nickname.clone();
+
+        let target = self
+            .players
+            .iter()
+            .find(|player| player.nickname == *nickname)
+            .ok_or(anyhow!("cant challenge, {} is not online", nickname))?;
+
+        if target.connection_id == connection_id {
+            return Err(anyhow!("cant challenge yourself"));
+        }
+
+        if target.status != PlayerStatus::Idle {
+            return Err(anyhow!("cant challenge, {} is busy", target.nickname));
+        }
+
+        self.challenge_count = self.challenge_count.wrapping_add(1);
+        let challenge = Challenge {
+            challenge_id: self.challenge_count,
+            challenger: connection_id,
+            target: target.connection_id,
+            preset,
+        };
+
+        self.notify(
+            challenge.target,
+            Notice::Challenged {
+                challenge_id: challenge.challenge_id,
+                nickname: challenger_nickname,
+                preset,
+            },
+        )?;
+        self.challenges.push(challenge);
+
+        return Ok(challenge.challenge_id);
+    }
+
+    // the challenger still has to be around and idle for the challenge to go ahead
+    pub fn accept(
+        &mut self,
+        connection_id: ConnectionId,
+        challenge_id: ChallengeId,
+    ) -> Result<Challenge> {
+        let challenge = self.take_challenge(connection_id, challenge_id)?;
+
+        let challenger = self.get_player(challenge.challenger)?;
+        if challenger.status != PlayerStatus::Idle {
+            return Err(anyhow!("cant accept, {} is busy", challenger.nickname));
+        }
+
+        return Ok(challenge);
+    }
+
+    pub fn decline(
+        &mut self,
+        connection_id: ConnectionId,
+        challenge_id: ChallengeId,
+    ) -> Result<()> {
+        let challenge = self.take_challenge(connection_id, challenge_id)?;
+        return self.notify(challenge.challenger, Notice::Declined { challenge_id });
+    }
+
+    pub fn notify(&self, connection_id: ConnectionId, notice: Notice) -> Result<()> {
+        return self
+            .get_player(connection_id)?
+            .sender
+            .send(notice)
+            .map_err(|_| anyhow!("player went offline"));
+    }
+
+    fn take_challenge(
+        &mut self,
+        connection_id: ConnectionId,
+        challenge_id: ChallengeId,
+    ) -> Result<Challenge> {
+        let index = self
+            .challenges
+            .iter()
+            .position(|challenge| {
+                challenge.challenge_id == challenge_id && challenge.target == connection_id
+            })
+            .ok_or(anyhow!("challenge not found"))?;
+
+        return Ok(self.challenges.remove(index));
+    }
+
+    fn get_player(&self, connection_id: ConnectionId) -> Result<&OnlinePlayer> {
+        return self
+            .players
+            .iter()
+            .find(|player| player.connection_id == connection_id)
+            .ok_or(anyhow!("player not online"));
+    }
+}
+
+#[cfg(test)]
+mod lobby_test {
+    use anyhow::Result;
+
+    use super::{Lobby, Notice, PlayerStatus};
+    use crate::{identity::ConnectionId, nickname::Nickname, preset::Preset};
+
+    #[test]
+    pub fn test_sign_in() -> Result<()> {
+        let mut lobby = Lobby::new();
+        let connection_a = ConnectionId::new();
+        let connection_b = ConnectionId::new();
+
+        lobby.sign_in(connection_a, Nickname::try_from("Ahab".as_bytes())?)?;
+        assert!(lobby
+            .sign_in(connection_b, Nickname::try_from("AHAB".as_bytes())?)
+            .is_err());
+
+        lobby.sign_in(connection_a, Nickname::try_from("Starbuck".as_bytes())?)?;
+        lobby.sign_in(connection_b, Nickname::try_from("Ahab".as_bytes())?)?;
+        lobby.set_status(connection_b, PlayerStatus::Playing, Some(1));
+
+        let players = lobby.get_players();
+        assert_eq!(players.len(), 2);
+        assert_eq!(players[0].nickname.as_str(), "Starbuck");
+        assert_eq!(players[1].status, PlayerStatus::Playing);
+
+        lobby.sign_out(connection_a);
+        assert_eq!(lobby.get_players().len(), 1);
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_challenge() -> Result<()> {
+        let mut lobby = Lobby::new();
+        let connection_a = ConnectionId::new();
+        let connection_b = ConnectionId::new();
+        let ahab = Nickname::try_from("Ahab".as_bytes())?;
+        let starbuck = Nickname::try_from("Starbuck".as_bytes())?;
+
+        let notices_a = lobby.sign_in(connection_a, ahab.clone())?;
+        let notices_b = lobby.sign_in(connection_b, starbuck.clone())?;
+
+        assert!(lobby
+            .challenge(connection_a, &ahab, Preset::Classic)
+            .is_err());
+
+        let challenge_id = lobby.challenge(connection_a, &starbuck, Preset::Salvo)?;
+        match notices_b.try_recv()? {
+            Notice::Challenged {
+                nickname, preset, ..
+            } => {
+                assert_eq!(nickname, ahab);
+                assert_eq!(preset, Preset::Salvo);
+            }
+            notice => panic!("unexpected notice {:?}", notice),
+        };
+
+        assert!(lobby.accept(connection_a, challenge_id).is_err());
+        lobby.decline(connection_b, challenge_id)?;
+        assert!(matches!(notices_a.try_recv()?, Notice::Declined { .. }));
+
+        let challenge_id = lobby.challenge(connection_a, &starbuck, Preset::Classic)?;
+        lobby.set_status(connection_a, PlayerStatus::Queued, None);
+        assert!(lobby.accept(connection_b, challenge_id).is_err());
+
+        lobby.set_status(connection_a, PlayerStatus::Idle, None);
+        let challenge_id = lobby.challenge(connection_a, &starbuck, Preset::Classic)?;
+        let challenge = lobby.accept(connection_b, challenge_id)?;
+        assert_eq!(challenge.challenger, connection_a);
+        assert!(lobby.accept(connection_b, challenge_id).is_err());
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,243 @@
+use std::{
+    collections::VecDeque,
+    sync::mpsc::{channel, Receiver, Sender},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+
+use crate::{
+    identity::{ConnectionId, SeatId},
+    nickname::Nickname,
+    preset::Preset,
+    room_registry::{Room, RoomId, RoomRegistry},
+};
+
+pub type TicketId = u32;
+
+// how many of the latest matches are averaged into the wait estimate
+const WAIT_SAMPLES: usize = 10;
+
+pub struct Match {
+    pub room_id: RoomId,
+    pub code: Option<String>,
+    pub game: Room,
+    pub player_id: SeatId,
+    pub receiver: Receiver<()>,
+}
+
+struct Ticket {
+    ticket_id: TicketId,
+    preset: Option<Preset>,
+    connection_id: ConnectionId,
+    nickname: Nickname,
+    joined_at: Instant,
+    sender: Sender<Match>,
+}
+
+pub struct Matchmaking {
+    queue: Vec<Ticket>,
+    ticket_count: TicketId,
+    waits: VecDeque<Duration>,
+}
+
+impl Matchmaking {
+    pub fn new() -> Self {
+        return Matchmaking {
+            queue: Vec::new(),
+            ticket_count: 0,
+            waits: VecDeque::new(),
+        };
+    }
+
+    pub fn enqueue(
+        &mut self,
+        preset: Option<Preset>,
+        connection_id: ConnectionId,
+        nickname: Nickname,
+    ) -> (TicketId, Receiver<Match>) {
+        let (sender, receiver) = channel();
+
+        self.ticket_count = self.ticket_count.wrapping_add(1);
+        self.queue.push(Ticket {
+            ticket_id: self.ticket_count,
+            preset,
+            connection_id,
+            nickname,
+            joined_at: Instant::now(),
+            sender,
+        });
+
+        return (self.ticket_count, receiver);
+    }
+
+    pub fn leave(&mut self, ticket_id: TicketId) {
+        self.queue.retain(|ticket| ticket.ticket_id != ticket_id);
+    }
+
+    pub fn get_position(&self, ticket_id: TicketId) -> Option<usize> {
+        return self
+            .queue
+            .iter()
+            .position(|ticket| ticket.ticket_id == ticket_id)
+            .map(|index| index + 1);
+    }
+
+    pub fn get_estimated_wait(&self) -> Duration {
+        if self.waits.is_empty() {
+            return Duration::ZERO;
+        }
+
+        return self.waits.iter().sum::<Duration>() / self.waits.len() as u32;
+    }
+
+    // the longest waiting player picks the rules, anyone without a preference
+    // plays whatever the others want
+    pub fn pair_players(&mut self, registry: &mut RoomRegistry) -> Result<()> {
+        let mut anchor = 0;
+
+        while anchor < self.queue.len() {
+            let preset = self.queue[anchor]
+                .preset
+                .or(self.queue[anchor..].iter().find_map(|ticket| ticket.preset))
+                .unwrap_or(Preset::Classic);
+            let rules = preset.get_rules();
+
+            // players sharing a nickname wait for different rooms
+            let mut players: Vec<usize> = Vec::new();
+            for index in anchor..self.queue.len() {
+                let ticket = &self.queue[index];
+                let is_taken = players
+                    .iter()
+                    .any(|other| self.queue[*other].nickname == ticket.nickname);
+
+                if ticket.preset.is_none_or(|other| other == preset) && !is_taken {
+                    players.push(index);
+                }
+            }
+            players.truncate(rules.player_count as usize);
+
+            if players.len() < rules.player_count as usize {
+                anchor += 1;
+                continue;
+            }
+
+            let (room_id, game) = registry.create_room(rules)?;
+
+            // everyone gets a seat before anyone leaves the queue, so that a failed
+            // match leaves the tickets where they were
+            let mut game_lock = game.lock().unwrap();
+            let mut seated = Vec::new();
+            for index in &players {
+                let ticket = &self.queue[*index];
+                match game_lock.connect_as(ticket.connection_id, ticket.nickname.clone()) {
+                    Ok(seat) => seated.push(seat),
+                    Err(error) => {
+                        for (player_id, _) in seated {
+                            game_lock.disconnect(player_id)?;
+                        }
+                        drop(game_lock);
+                        registry.cleanup();
+
+                        return Err(error);
+                    }
+                }
+            }
+            drop(game_lock);
+
+            let mut tickets: Vec<Ticket> = players
+                .into_iter()
+                .rev()
+                .map(|index| self.queue.remove(index))
+                .collect();
+            tickets.reverse();
+
+            for (ticket, (player_id, receiver)) in tickets.into_iter().zip(seated) {
+                self.waits.push_back(ticket.joined_at.elapsed());
+                if self.waits.len() > WAIT_SAMPLES {
+                    self.waits.pop_front();
+                }
+
+                let found = Match {
+                    room_id,
+                    code: None,
+                    game: game.clone(),
+                    player_id,
+                    receiver,
+                };
+
+                // a player who dropped out in the meantime gives up the seat
+                if ticket.sender.send(found).is_err() {
+                    game.lock().unwrap().leave(player_id)?;
+                }
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod matchmaking_test {
+    use anyhow::Result;
+
+    use super::Matchmaking;
+    use crate::{
+        identity::ConnectionId, nickname::Nickname, preset::Preset, room_registry::RoomRegistry,
+    };
+
+    #[test]
+    pub fn test_pair_players() -> Result<()> {
+        let mut registry = RoomRegistry::new(4);
+        let mut matchmaking = Matchmaking::new();
+
+        let nickname = |name: &str| Nickname::try_from(name.as_bytes());
+
+        let (ticket_a, receiver_a) =
+            matchmaking.enqueue(Some(Preset::Salvo), ConnectionId::new(), nickname("a")?);
+        let (ticket_b, _) =
+            matchmaking.enqueue(Some(Preset::Classic), ConnectionId::new(), nickname("b")?);
+        let (ticket_c, _) =
+            matchmaking.enqueue(Some(Preset::Salvo), ConnectionId::new(), nickname("A")?);
+        let (_, receiver_d) = matchmaking.enqueue(None, ConnectionId::new(), nickname("d")?);
+
+        assert_eq!(matchmaking.get_position(ticket_b), Some(2));
+
+        matchmaking.pair_players(&mut registry)?;
+
+        let match_a = receiver_a.try_recv()?;
+        let match_d = receiver_d.try_recv()?;
+
+        assert_eq!(match_a.room_id, match_d.room_id);
+        assert!(match_a.game.lock().unwrap().rules.simultaneous_turns);
+        assert_eq!(matchmaking.get_position(ticket_a), None);
+        assert_eq!(matchmaking.get_position(ticket_b), Some(1));
+        assert_eq!(matchmaking.get_position(ticket_c), Some(2));
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_pair_players_failed() -> Result<()> {
+        let mut registry = RoomRegistry::new(0);
+        let mut matchmaking = Matchmaking::new();
+
+        let nickname = |name: &str| Nickname::try_from(name.as_bytes());
+
+        let (ticket_a, receiver_a) = matchmaking.enqueue(None, ConnectionId::new(), nickname("a")?);
+        let (ticket_b, _) = matchmaking.enqueue(None, ConnectionId::new(), nickname("b")?);
+
+        assert!(matchmaking.pair_players(&mut registry).is_err());
+        assert_eq!(matchmaking.get_position(ticket_a), Some(1));
+        assert_eq!(matchmaking.get_position(ticket_b), Some(2));
+        assert_eq!(registry.get_room_count(), 0);
+
+        registry.max_rooms = 1;
+        matchmaking.pair_players(&mut registry)?;
+
+        assert!(receiver_a.try_recv().is_ok());
+        assert_eq!(matchmaking.get_position(ticket_a), None);
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,67 @@
+use std::fmt;
+
+use anyhow::{anyhow, Error, Result};
+
+pub const MAX_NICKNAME_LENGTH: usize = 16;
+
+// display name a player picks in the hello message, surrounding whitespace trimmed
+#[derive(Clone, Debug)]
+pub struct Nickname(String);
+
+impl Nickname {
+    pub fn as_str(&self) -> &str {
+        return &self.0;
+    }
+}
+
+// names that only differ by case would be too easy to confuse
+impl PartialEq for Nickname {
+    fn eq(&self, other: &Self) -> bool {
+        return self.0.to_lowercase() == other.0.to_lowercase();
+    }
+}
+
+impl TryFrom<&[u8]> for Nickname {
+    type Error = Error;
+
+    fn try_from(value: &[u8]) -> Result<Self> {
+        let name = String::from_utf8(value.to_vec())?.trim().to_string();
+
+        if name.is_empty() || name.chars().count() > MAX_NICKNAME_LENGTH {
+            return Err(anyhow!(
+                "nickname must be 1 to {} characters long",
+                MAX_NICKNAME_LENGTH
+            ));
+        }
+
+        if name.chars().any(|c| c.is_control()) {
+            return Err(anyhow!("nickname cant contain control characters"));
+        }
+
+        return Ok(Nickname(name));
+    }
+}
+
+impl fmt::Display for Nickname {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}", self.0);
+    }
+}
+
+#[cfg(test)]
+mod nickname_test {
+    use super::Nickname;
+
+    #[test]
+    pub fn test_from_bytes() {
+        let nickname = Nickname::try_from(" Ahab ".as_bytes()).unwrap();
+        assert_eq!(nickname.as_str(), "Ahab");
+        assert_eq!(nickname, Nickname::try_from("AHAB".as_bytes()).unwrap());
+
+        assert!(Nickname::try_from("  ".as_bytes()).is_err());
+        assert!(Nickname::try_from("Captain Ahab of the Pequod".as_bytes()).is_err());
+        assert!(Nickname::try_from("Ahab\n".as_bytes()).is_ok());
+        assert!(Nickname::try_from("A\u{7}hab".as_bytes()).is_err());
+        assert!(Nickname::try_from(&[0xff, 0xfe][..]).is_err());
+    }
+}
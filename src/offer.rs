@@ -0,0 +1,36 @@
+use anyhow::{anyhow, Error, Result};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(u8)]
+pub enum Offer {
+    Rematch = 1,
+    Draw = 2,
+    Pause = 3,
+    Resume = 4,
+}
+
+impl TryFrom<u8> for Offer {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        return match value {
+            1 => Ok(Offer::Rematch),
+            2 => Ok(Offer::Draw),
+            3 => Ok(Offer::Pause),
+            4 => Ok(Offer::Resume),
+            _ => Err(anyhow!("unknown offer")),
+        };
+    }
+}
+
+#[cfg(test)]
+mod offer_test {
+    use super::Offer;
+
+    #[test]
+    pub fn test_from_u8() {
+        assert_eq!(Offer::try_from(1).unwrap(), Offer::Rematch);
+        assert_eq!(Offer::try_from(4).unwrap(), Offer::Resume);
+        assert!(Offer::try_from(0).is_err());
+    }
+}
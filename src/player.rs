@@ -2,30 +2,75 @@ use anyhow::{anyhow, Result};
 
 use crate::{
     playmap::Playmap,
-    point::{Orientation, Point},
+    point::{Direction, Orientation, Point, BOARD_SIZE},
     ship::{Ship, BATTLESHIP_SIZE, CRUISER_SIZE, DESTROYER_SIZE, SUBMARINE_SIZE},
 };
 
 pub struct Player {
+    pub board_size: u8,
+
     pub ships: Playmap,
     pub shots: Playmap,
+    pub pings: Playmap,
+
+    // one entry per cell, row by row
+    pub armor: Vec<u8>,
+    pub damage: Vec<u8>,
 
     pub submarines: u8,
     pub destroyers: u8,
     pub cruisers: u8,
     pub battleships: u8,
+
+    pub repairs: u8,
+    pub repair_cooldown: u8,
+
+    pub shots_fired: u8,
+    pub damage_dealt: u16,
+    pub sonar_pings: u8,
+    pub hints_used: u8,
 }
 
 impl Player {
     pub fn new() -> Self {
+        return Player::with_size(BOARD_SIZE);
+    }
+
+    pub fn with_size(board_size: u8) -> Self {
+        let cells = board_size as usize * board_size as usize;
+
         return Player {
-            ships: Playmap::new(),
-            shots: Playmap::new(),
+            board_size,
+
+            ships: Playmap::with_size(board_size),
+            shots: Playmap::with_size(board_size),
+            pings: Playmap::with_size(board_size),
+
+            armor: vec![0; cells],
+            damage: vec![0; cells],
 
             submarines: 0,
             destroyers: 0,
             cruisers: 0,
             battleships: 0,
+
+            repairs: 0,
+            repair_cooldown: 0,
+
+            shots_fired: 0,
+            damage_dealt: 0,
+            sonar_pings: 0,
+            hints_used: 0,
+        };
+    }
+
+    pub fn get_fleet_size(&self, size: u8) -> u8 {
+        return match size {
+            SUBMARINE_SIZE => self.submarines,
+            DESTROYER_SIZE => self.destroyers,
+            CRUISER_SIZE => self.cruisers,
+            BATTLESHIP_SIZE => self.battleships,
+            _ => 0,
         };
     }
 
@@ -34,17 +79,44 @@ impl Player {
     }
 
     pub fn get_hits(&self) -> Playmap {
-        let value = self.ships.value & self.shots.value;
-        return Playmap { value };
+        let mut hits = Playmap::with_size(self.board_size);
+
+        for point in self.get_shot_ships() {
+            let index = point.get_index(self.board_size);
+            if self.damage[index] >= self.armor[index] {
+                hits.mark_field(point);
+            }
+        }
+
+        return hits;
+    }
+
+    pub fn get_damaged(&self) -> Playmap {
+        let mut damaged = Playmap::with_size(self.board_size);
+
+        for point in self.get_shot_ships() {
+            let index = point.get_index(self.board_size);
+            if self.damage[index] > 0 && self.damage[index] < self.armor[index] {
+                damaged.mark_field(point);
+            }
+        }
+
+        return damaged;
     }
 
     pub fn has_intact_ships(&self) -> bool {
-        return self.get_hits().value.count_ones() < self.ships.value.count_ones();
+        return self.get_hits().count() < self.ships.count();
     }
 
-    pub fn place_figure(&mut self, ship: Ship, point: Point) -> Result<()> {
-        for point in ship.get_points(point)? {
+    pub fn place_figure(&mut self, ship: Ship, point: Point, armor: u8) -> Result<()> {
+        let points = ship.get_points(point, self.board_size)?;
+        if !self.is_free_area(&points, &[]) {
+            return Err(anyhow!("cant place ship next to another ship"));
+        }
+
+        for point in points {
             self.ships.mark_field(point);
+            self.armor[point.get_index(self.board_size)] = armor;
         }
 
         match ship {
@@ -58,69 +130,175 @@ impl Player {
     }
 
     pub fn remove_figure(&mut self, point: Point) -> Result<()> {
+        let points = self.get_figure(point)?;
+
+        for point in &points {
+            self.ships.demark_field(*point);
+            self.armor[point.get_index(self.board_size)] = 0;
+        }
+
+        let count = match points.len() as u8 {
+            SUBMARINE_SIZE => &mut self.submarines,
+            DESTROYER_SIZE => &mut self.destroyers,
+            CRUISER_SIZE => &mut self.cruisers,
+            BATTLESHIP_SIZE => &mut self.battleships,
+            _ => panic!("size does not match any known ship type"),
+        };
+
+        *count -= 1;
+
+        return Ok(());
+    }
+
+    pub fn get_figure(&self, point: Point) -> Result<Vec<Point>> {
         if !self.ships.is_marked_field(point) {
             return Err(anyhow!("no ship at this point"));
-        } else {
-            self.ships.demark_field(point);
         }
 
-        let mut length = 1;
+        let mut points = vec![point];
 
-        let next = point;
-        while let Ok(next) = next.get_prev(&Orientation::Vertical) {
-            if !self.ships.is_marked_field(next) {
-                break;
-            } else {
-                self.ships.demark_field(next);
-                length += 1;
+        for orientation in [Orientation::Vertical, Orientation::Horizontal] {
+            let mut next = point;
+            while let Ok(prev) = next.get_prev(&orientation) {
+                if !self.ships.is_marked_field(prev) {
+                    break;
+                }
+                points.push(prev);
+                next = prev;
             }
-        }
 
-        let next = point;
-        while let Ok(next) = next.get_next(&Orientation::Vertical) {
-            if !self.ships.is_marked_field(next) {
-                break;
-            } else {
-                self.ships.demark_field(next);
-                length += 1;
+            let mut next = point;
+            while let Ok(following) = next.get_next(&orientation, self.board_size) {
+                if !self.ships.is_marked_field(following) {
+                    break;
+                }
+                points.push(following);
+                next = following;
             }
         }
 
-        let next = point;
-        while let Ok(next) = next.get_prev(&Orientation::Horizontal) {
-            if !self.ships.is_marked_field(next) {
-                break;
-            } else {
-                self.ships.demark_field(next);
-                length += 1;
+        return Ok(points);
+    }
+
+    pub fn register_shot(&mut self, point: Point) -> bool {
+        self.shots.mark_field(point);
+
+        let index = point.get_index(self.board_size);
+        if self.has_ship_at(point) && self.damage[index] < self.armor[index] {
+            self.damage[index] += 1;
+            return true;
+        }
+
+        return false;
+    }
+
+    // sonar reveals ships in the 3x3 area around the point
+    pub fn register_ping(&mut self, point: Point) {
+        let last = self.board_size.saturating_sub(1);
+        for y in point.y.saturating_sub(1)..=(point.y + 1).min(last) {
+            for x in point.x.saturating_sub(1)..=(point.x + 1).min(last) {
+                if self.has_ship_at(Point { x, y }) {
+                    self.pings.mark_field(Point { x, y });
+                }
             }
         }
+    }
+
+    pub fn move_figure(&mut self, point: Point, direction: Direction) -> Result<()> {
+        let points = self.get_figure(point)?;
+
+        if points
+            .iter()
+            .any(|point| self.damage[point.get_index(self.board_size)] > 0)
+        {
+            return Err(anyhow!("cant move a damaged ship"));
+        }
+
+        let armor = self.armor[point.get_index(self.board_size)];
+
+        let mut moved = Vec::new();
+        for point in &points {
+            moved.push(point.get_adjacent(&direction, self.board_size)?);
+        }
+
+        if !self.is_free_area(&moved, &points) {
+            return Err(anyhow!("cant move ship next to another ship"));
+        }
+
+        for point in &points {
+            self.ships.demark_field(*point);
+            self.armor[point.get_index(self.board_size)] = 0;
+        }
+
+        for point in &moved {
+            self.ships.mark_field(*point);
+            self.armor[point.get_index(self.board_size)] = armor;
+        }
+
+        return Ok(());
+    }
 
-        let next = point;
-        while let Ok(next) = next.get_next(&Orientation::Horizontal) {
-            if !self.ships.is_marked_field(next) {
-                break;
-            } else {
-                self.ships.demark_field(next);
-                length += 1;
+    // ships are told apart by the gaps between them, so a ship may neither
+    // overlap nor touch another one, except for the cells being ignored
+    pub fn is_free_area(&self, points: &[Point], ignored: &[Point]) -> bool {
+        for point in points {
+            let mut neighbours = vec![*point];
+            for direction in [
+                Direction::Up,
+                Direction::Down,
+                Direction::Left,
+                Direction::Right,
+            ] {
+                if let Ok(neighbour) = point.get_adjacent(&direction, self.board_size) {
+                    neighbours.push(neighbour);
+                }
+            }
+
+            for neighbour in neighbours {
+                if self.has_ship_at(neighbour) && !ignored.contains(&neighbour) {
+                    return false;
+                }
             }
         }
 
-        let count = match length {
-            SUBMARINE_SIZE => &mut self.submarines,
-            DESTROYER_SIZE => &mut self.destroyers,
-            CRUISER_SIZE => &mut self.cruisers,
-            BATTLESHIP_SIZE => &mut self.battleships,
-            _ => panic!("size does not match any known ship type"),
-        };
+        return true;
+    }
 
-        *count -= 1;
+    pub fn repair_figure(&mut self, point: Point) -> Result<()> {
+        let index = point.get_index(self.board_size);
+        if !self.has_ship_at(point) || self.damage[index] == 0 {
+            return Err(anyhow!("no damage at this point"));
+        }
+
+        let is_sunk = self.get_figure(point)?.iter().all(|point| {
+            let index = point.get_index(self.board_size);
+            return self.damage[index] >= self.armor[index];
+        });
+
+        if is_sunk {
+            return Err(anyhow!("cant repair a sunk ship"));
+        }
+
+        self.damage[index] -= 1;
+        if self.damage[index] == 0 {
+            self.shots.demark_field(point);
+        }
+
+        self.repairs += 1;
 
         return Ok(());
     }
 
-    pub fn register_shot(&mut self, point: Point) {
-        self.shots.mark_field(point);
+    fn get_shot_ships(&self) -> Vec<Point> {
+        let mut points = Vec::new();
+
+        for point in Point::all(self.board_size) {
+            if self.ships.is_marked_field(point) && self.shots.is_marked_field(point) {
+                points.push(point);
+            }
+        }
+
+        return points;
     }
 }
 
@@ -129,7 +307,7 @@ mod test_player {
     use anyhow::Result;
 
     use crate::playmap::Playmap;
-    use crate::point::{Orientation, Point};
+    use crate::point::{Direction, Orientation, Point};
     use crate::ship::Ship;
 
     use super::Player;
@@ -152,6 +330,36 @@ mod test_player {
         assert_eq!(player.get_hits().value, 0b0001 << 124);
     }
 
+    #[test]
+    pub fn test_get_damaged() -> Result<()> {
+        let mut player = Player::new();
+        let ship = Ship::Destroyer(Orientation::Horizontal);
+
+        player.place_figure(ship, Point { x: 0, y: 0 }, 2)?;
+
+        player.register_shot(Point { x: 0, y: 0 });
+        assert_eq!(player.get_damaged().value, 0b1000 << 124);
+        assert_eq!(player.get_hits().value, 0b0000 << 124);
+
+        player.register_shot(Point { x: 0, y: 0 });
+        assert_eq!(player.get_damaged().value, 0b0000 << 124);
+        assert_eq!(player.get_hits().value, 0b1000 << 124);
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_register_ping() -> Result<()> {
+        let mut player = Player::new();
+        player.place_figure(Ship::Submarine, Point { x: 0, y: 0 }, 1)?;
+        player.place_figure(Ship::Submarine, Point { x: 3, y: 0 }, 1)?;
+
+        player.register_ping(Point { x: 1, y: 1 });
+        assert_eq!(player.pings.value, 0b1000 << 124);
+
+        return Ok(());
+    }
+
     #[test]
     pub fn has_intact_ships() {
         let mut player = Player::new();
@@ -166,9 +374,17 @@ mod test_player {
         let mut player = Player::new();
         let ship = Ship::Destroyer(Orientation::Horizontal);
 
-        player.place_figure(ship, Point { x: 1, y: 0 })?;
+        player.place_figure(ship, Point { x: 1, y: 0 }, 1)?;
         assert_eq!(player.ships.value, 0b0110 << 124);
 
+        assert!(player
+            .place_figure(Ship::Submarine, Point { x: 3, y: 0 }, 1)
+            .is_err());
+        assert!(player
+            .place_figure(Ship::Submarine, Point { x: 2, y: 0 }, 1)
+            .is_err());
+        assert_eq!(player.submarines, 0);
+
         return Ok(());
     }
 
@@ -185,10 +401,81 @@ mod test_player {
     }
 
     #[test]
-    pub fn test_register_shot() {
+    pub fn test_get_figure() -> Result<()> {
+        let mut player = Player::new();
+        player.ships = Playmap::from(0b0111_1000_0000 << 116);
+
+        assert_eq!(player.get_figure(Point { x: 3, y: 0 })?.len(), 4);
+        assert!(player.get_figure(Point { x: 0, y: 0 }).is_err());
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_move_figure() -> Result<()> {
+        let mut player = Player::new();
+        let ship = Ship::Destroyer(Orientation::Horizontal);
+
+        player.place_figure(ship, Point { x: 0, y: 0 }, 1)?;
+        player.place_figure(Ship::Submarine, Point { x: 4, y: 0 }, 1)?;
+
+        player.move_figure(Point { x: 0, y: 0 }, Direction::Right)?;
+        assert_eq!(player.ships.value, 0b0110_1000_0000 << 116);
+
+        assert!(player
+            .move_figure(Point { x: 1, y: 0 }, Direction::Up)
+            .is_err());
+        assert!(player
+            .move_figure(Point { x: 1, y: 0 }, Direction::Right)
+            .is_err());
+
+        player.register_shot(Point { x: 1, y: 0 });
+        assert!(player
+            .move_figure(Point { x: 1, y: 0 }, Direction::Down)
+            .is_err());
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_is_free_area() {
+        let mut player = Player::new();
+        player.ships = Playmap::from(0b0100 << 124);
+
+        assert_eq!(player.is_free_area(&[Point { x: 3, y: 0 }], &[]), true);
+        assert_eq!(player.is_free_area(&[Point { x: 2, y: 0 }], &[]), false);
+
+        let ignored = [Point { x: 1, y: 0 }];
+        assert_eq!(player.is_free_area(&[Point { x: 2, y: 0 }], &ignored), true);
+    }
+
+    #[test]
+    pub fn test_repair_figure() -> Result<()> {
         let mut player = Player::new();
+        let ship = Ship::Destroyer(Orientation::Horizontal);
+
+        player.place_figure(ship, Point { x: 0, y: 0 }, 1)?;
+        assert!(player.repair_figure(Point { x: 0, y: 0 }).is_err());
+
+        player.register_shot(Point { x: 0, y: 0 });
+        player.repair_figure(Point { x: 0, y: 0 })?;
 
+        assert_eq!(player.get_hits().value, 0b0000 << 124);
+        assert_eq!(player.shots.value, 0b0000 << 124);
+        assert_eq!(player.repairs, 1);
+
+        player.register_shot(Point { x: 0, y: 0 });
         player.register_shot(Point { x: 1, y: 0 });
+        assert!(player.repair_figure(Point { x: 0, y: 0 }).is_err());
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_register_shot() {
+        let mut player = Player::new();
+
+        assert_eq!(player.register_shot(Point { x: 1, y: 0 }), false);
         assert_eq!(player.shots.value, 0b0100 << 124);
     }
 }
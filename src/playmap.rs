@@ -1,40 +1,91 @@
-use crate::point::Point;
+use crate::point::{Point, BOARD_SIZE};
 
+// a board of up to 128 cells fits in `value`, a larger one continues in `rest`
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Playmap {
     pub value: u128,
+    pub rest: u128,
+    pub board_size: u8,
 }
 
 impl Playmap {
     pub fn new() -> Self {
-        return Playmap { value: 0 };
+        return Playmap::with_size(BOARD_SIZE);
+    }
+
+    pub fn with_size(board_size: u8) -> Self {
+        return Playmap {
+            value: 0,
+            rest: 0,
+            board_size,
+        };
     }
 
     pub fn is_marked_field(&self, point: Point) -> bool {
-        let point_index = point.y * 10 + point.x + 1;
-        let bit_index = 128 - point_index;
+        if !point.is_on_board(self.board_size) {
+            return false;
+        }
 
-        return self.value >> bit_index & 0b1 == 1;
+        let (bits, bit_index) = self.get_bit(point);
+
+        return bits >> bit_index & 0b1 == 1;
     }
 
     pub fn mark_field(&mut self, point: Point) {
-        let point_index = point.y * 10 + point.x + 1;
-        let bit_index = 128 - point_index;
+        if !point.is_on_board(self.board_size) {
+            return;
+        }
 
-        self.value |= 1 << bit_index;
+        let (_, bit_index) = self.get_bit(point);
+        *self.get_bits_mut(point) |= 1 << bit_index;
     }
 
     pub fn demark_field(&mut self, point: Point) {
-        let point_index = point.y * 10 + point.x + 1;
-        let bit_index = 128 - point_index;
+        if !point.is_on_board(self.board_size) {
+            return;
+        }
+
+        let (_, bit_index) = self.get_bit(point);
+        *self.get_bits_mut(point) &= !(1 << bit_index);
+    }
+
+    pub fn count(&self) -> u32 {
+        return self.value.count_ones() + self.rest.count_ones();
+    }
+
+    // boards up to 11x11 go out as 128 bits, larger ones as 256
+    pub fn to_be_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.value.to_be_bytes().to_vec();
+        if self.board_size as usize * self.board_size as usize > 128 {
+            bytes.extend(self.rest.to_be_bytes());
+        }
 
-        self.value &= !(1 << bit_index);
+        return bytes;
+    }
+
+    fn get_bit(&self, point: Point) -> (u128, usize) {
+        let point_index = point.get_index(self.board_size) + 1;
+
+        return match point_index <= 128 {
+            true => (self.value, 128 - point_index),
+            false => (self.rest, 256 - point_index),
+        };
+    }
+
+    fn get_bits_mut(&mut self, point: Point) -> &mut u128 {
+        return match point.get_index(self.board_size) < 128 {
+            true => &mut self.value,
+            false => &mut self.rest,
+        };
     }
 }
 
 impl From<u128> for Playmap {
     fn from(value: u128) -> Self {
-        return Playmap { value };
+        return Playmap {
+            value,
+            ..Playmap::new()
+        };
     }
 }
 
@@ -71,4 +122,24 @@ mod playmap_test {
         map.demark_field(Point { x: 2, y: 0 });
         assert_eq!(map, Playmap::from(0b0000 << 124));
     }
+
+    #[test]
+    pub fn test_large_board() {
+        let mut map = Playmap::with_size(15);
+
+        map.mark_field(Point { x: 14, y: 14 });
+        map.mark_field(Point { x: 12, y: 0 });
+        map.mark_field(Point { x: 0, y: 12 });
+
+        assert_eq!(map.rest, 0b1 << 75 | 0b1 << 31);
+        assert!(map.is_marked_field(Point { x: 14, y: 14 }));
+        assert!(map.is_marked_field(Point { x: 12, y: 0 }));
+        assert!(!map.is_marked_field(Point { x: 0, y: 13 }));
+        assert_eq!(map.count(), 3);
+        assert_eq!(map.to_be_bytes().len(), 32);
+
+        map.mark_field(Point { x: 15, y: 0 });
+        assert_eq!(map.count(), 3);
+        assert_eq!(Playmap::new().to_be_bytes().len(), 16);
+    }
 }
@@ -6,6 +6,18 @@ pub enum Orientation {
     Vertical = 2,
 }
 
+#[derive(Clone, Copy)]
+pub enum Direction {
+    Up = 1,
+    Down = 2,
+    Left = 3,
+    Right = 4,
+}
+
+// a point fits in a byte, 4 bits per coordinate, so no board is larger than 16x16
+pub const BOARD_SIZE: u8 = 10;
+pub const MAX_BOARD_SIZE: u8 = 16;
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Point {
     pub x: u8,
@@ -14,17 +26,33 @@ pub struct Point {
 
 impl Point {
     pub fn new(x: u8, y: u8) -> Result<Self> {
-        if x > 9 || y > 9 {
-            return Err(anyhow!("coordinate is bound to 0..9 range"));
+        if x >= MAX_BOARD_SIZE || y >= MAX_BOARD_SIZE {
+            return Err(anyhow!("coordinate is bound to 0..15 range"));
         }
 
         return Ok(Point { x, y });
     }
 
-    pub fn get_next(&self, orientation: &Orientation) -> Result<Self> {
+    // every point on the board, row by row
+    pub fn all(board_size: u8) -> impl Iterator<Item = Point> {
+        return (0..board_size as u16 * board_size as u16).map(move |index| Point {
+            x: (index % board_size as u16) as u8,
+            y: (index / board_size as u16) as u8,
+        });
+    }
+
+    pub fn is_on_board(&self, board_size: u8) -> bool {
+        return self.x < board_size && self.y < board_size;
+    }
+
+    pub fn get_index(&self, board_size: u8) -> usize {
+        return self.y as usize * board_size as usize + self.x as usize;
+    }
+
+    pub fn get_next(&self, orientation: &Orientation, board_size: u8) -> Result<Self> {
         match orientation {
             Orientation::Horizontal => {
-                if self.x == 9 {
+                if self.x + 1 >= board_size {
                     return Err(anyhow!("next point is out of range"));
                 }
 
@@ -34,7 +62,7 @@ impl Point {
                 });
             }
             Orientation::Vertical => {
-                if self.y == 9 {
+                if self.y + 1 >= board_size {
                     return Err(anyhow!("next point is out of range"));
                 }
 
@@ -70,6 +98,29 @@ impl Point {
             }
         }
     }
+
+    pub fn get_adjacent(&self, direction: &Direction, board_size: u8) -> Result<Self> {
+        return match direction {
+            Direction::Up => self.get_prev(&Orientation::Vertical),
+            Direction::Down => self.get_next(&Orientation::Vertical, board_size),
+            Direction::Left => self.get_prev(&Orientation::Horizontal),
+            Direction::Right => self.get_next(&Orientation::Horizontal, board_size),
+        };
+    }
+}
+
+impl TryFrom<u8> for Direction {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        return match value {
+            1 => Ok(Direction::Up),
+            2 => Ok(Direction::Down),
+            3 => Ok(Direction::Left),
+            4 => Ok(Direction::Right),
+            _ => Err(anyhow!("unknown direction")),
+        };
+    }
 }
 
 impl TryFrom<u8> for Point {
@@ -87,27 +138,53 @@ impl TryFrom<u8> for Point {
 mod point_test {
     use anyhow::Result;
 
-    use crate::point::{Orientation, Point};
+    use crate::point::{Direction, Orientation, Point, BOARD_SIZE};
 
     #[test]
     pub fn test_from_u8() -> Result<()> {
         assert_eq!(Point::try_from(0b0000_0000)?, Point { x: 0, y: 0 });
         assert_eq!(Point::try_from(0b1001_1000)?, Point { x: 9, y: 8 });
+        assert_eq!(Point::try_from(0b1111_1110)?, Point { x: 15, y: 14 });
 
         return Ok(());
     }
 
+    #[test]
+    pub fn test_all() {
+        let points: Vec<Point> = Point::all(BOARD_SIZE).collect();
+
+        assert_eq!(points.len(), 100);
+        assert_eq!(points[23], Point { x: 3, y: 2 });
+        assert!(points
+            .iter()
+            .enumerate()
+            .all(|(index, point)| point.get_index(BOARD_SIZE) == index));
+
+        assert_eq!(Point::all(16).count(), 256);
+    }
+
+    #[test]
+    pub fn test_get_index() {
+        assert_eq!(Point { x: 0, y: 0 }.get_index(BOARD_SIZE), 0);
+        assert_eq!(Point { x: 3, y: 2 }.get_index(BOARD_SIZE), 23);
+        assert_eq!(Point { x: 9, y: 9 }.get_index(BOARD_SIZE), 99);
+        assert_eq!(Point { x: 3, y: 2 }.get_index(15), 33);
+    }
+
     #[test]
     pub fn test_get_next() -> Result<()> {
-        let next = Point { x: 0, y: 0 }.get_next(&Orientation::Horizontal)?;
+        let next = Point { x: 0, y: 0 }.get_next(&Orientation::Horizontal, BOARD_SIZE)?;
         assert_eq!(next, Point { x: 1, y: 0 });
 
-        let next = next.get_next(&Orientation::Vertical)?;
+        let next = next.get_next(&Orientation::Vertical, BOARD_SIZE)?;
         assert_eq!(next, Point { x: 1, y: 1 });
 
-        let next = Point { x: 9, y: 0 }.get_next(&Orientation::Horizontal);
+        let next = Point { x: 9, y: 0 }.get_next(&Orientation::Horizontal, BOARD_SIZE);
         assert!(next.is_err());
 
+        let next = Point { x: 9, y: 0 }.get_next(&Orientation::Horizontal, 15)?;
+        assert_eq!(next, Point { x: 10, y: 0 });
+
         return Ok(());
     }
 
@@ -124,4 +201,20 @@ mod point_test {
 
         return Ok(());
     }
+
+    #[test]
+    pub fn test_get_adjacent() -> Result<()> {
+        let point = Point { x: 1, y: 1 };
+
+        let adjacent = |direction| point.get_adjacent(&direction, BOARD_SIZE);
+        assert_eq!(adjacent(Direction::Up)?, Point { x: 1, y: 0 });
+        assert_eq!(adjacent(Direction::Down)?, Point { x: 1, y: 2 });
+        assert_eq!(adjacent(Direction::Left)?, Point { x: 0, y: 1 });
+        assert_eq!(adjacent(Direction::Right)?, Point { x: 2, y: 1 });
+
+        let adjacent = Point { x: 0, y: 0 }.get_adjacent(&Direction::Up, BOARD_SIZE);
+        assert!(adjacent.is_err());
+
+        return Ok(());
+    }
 }
@@ -0,0 +1,90 @@
+use anyhow::{anyhow, Error, Result};
+
+use crate::game_rules::GameRules;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(u8)]
+pub enum Preset {
+    Classic = 1,
+    Armored = 2,
+    Salvo = 3,
+    FreeForAll = 4,
+    TwoOnTwo = 5,
+    CoOp = 6,
+    Convoy = 7,
+}
+
+impl Preset {
+    pub fn get_rules(&self) -> GameRules {
+        let rules = GameRules::new();
+
+        return match self {
+            Preset::Classic => rules,
+            Preset::Armored => GameRules {
+                cruiser_armor: 2,
+                battleship_armor: 2,
+                repair_limit: 2,
+                repair_cooldown: 2,
+                ..rules
+            },
+            Preset::Salvo => GameRules {
+                simultaneous_turns: true,
+                ..rules
+            },
+            Preset::FreeForAll => GameRules {
+                player_count: 3,
+                ..rules
+            },
+            Preset::TwoOnTwo => GameRules {
+                player_count: 4,
+                team_size: 2,
+                ..rules
+            },
+            Preset::CoOp => GameRules {
+                armada_size: 2,
+                ..rules
+            },
+            Preset::Convoy => GameRules {
+                shot_limit: 40,
+                sonar_limit: 3,
+                convoy_rounds: 30,
+                ..rules
+            },
+        };
+    }
+}
+
+impl TryFrom<u8> for Preset {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        return match value {
+            1 => Ok(Preset::Classic),
+            2 => Ok(Preset::Armored),
+            3 => Ok(Preset::Salvo),
+            4 => Ok(Preset::FreeForAll),
+            5 => Ok(Preset::TwoOnTwo),
+            6 => Ok(Preset::CoOp),
+            7 => Ok(Preset::Convoy),
+            _ => Err(anyhow!("unknown preset")),
+        };
+    }
+}
+
+#[cfg(test)]
+mod preset_test {
+    use super::Preset;
+
+    #[test]
+    pub fn test_from_u8() {
+        assert_eq!(Preset::try_from(1).unwrap(), Preset::Classic);
+        assert_eq!(Preset::try_from(7).unwrap(), Preset::Convoy);
+        assert!(Preset::try_from(0).is_err());
+    }
+
+    #[test]
+    pub fn test_get_rules() {
+        assert_eq!(Preset::TwoOnTwo.get_rules().get_team_count(), 2);
+        assert_eq!(Preset::CoOp.get_rules().get_seat_count(), 3);
+    }
+}
@@ -0,0 +1,85 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+use anyhow::{anyhow, Result};
+
+// xorshift generator, good enough for coin flips and not meant for anything secret
+#[derive(Clone, Copy)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        // xorshift gets stuck on zero, so nudge it off
+        return Random { state: seed.max(1) };
+    }
+
+    // std seeds its hashers from the OS, which saves us pulling in a crate
+    pub fn from_entropy() -> Self {
+        return Random::new(RandomState::new().build_hasher().finish());
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+
+        return self.state;
+    }
+
+    pub fn next_bool(&mut self) -> bool {
+        return self.next_u64() & 0b1 == 1;
+    }
+
+    pub fn next_below(&mut self, bound: usize) -> usize {
+        return (self.next_u64() % bound.max(1) as u64) as usize;
+    }
+}
+
+// secrets like session tokens and join codes come straight from the OS
+pub fn get_secure_bytes<const N: usize>() -> Result<[u8; N]> {
+    let mut bytes = [0; N];
+    getrandom::getrandom(&mut bytes).map_err(|_| anyhow!("cant get secure random bytes"))?;
+
+    return Ok(bytes);
+}
+
+#[cfg(test)]
+mod random_test {
+    use anyhow::Result;
+
+    use super::{get_secure_bytes, Random};
+
+    #[test]
+    pub fn test_next_u64() {
+        let mut random = Random::new(42);
+        let mut same_seed = Random::new(42);
+
+        assert_eq!(random.next_u64(), same_seed.next_u64());
+        assert_ne!(random.next_u64(), random.next_u64());
+    }
+
+    #[test]
+    pub fn test_next_below() {
+        let mut random = Random::new(42);
+
+        assert!((0..100).all(|_| random.next_below(3) < 3));
+    }
+
+    #[test]
+    pub fn test_zero_seed() {
+        let mut random = Random::new(0);
+
+        assert_ne!(random.next_u64(), 0);
+    }
+
+    #[test]
+    pub fn test_get_secure_bytes() -> Result<()> {
+        assert_ne!(get_secure_bytes::<16>()?, get_secure_bytes::<16>()?);
+
+        return Ok(());
+    }
+}
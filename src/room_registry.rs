@@ -0,0 +1,308 @@
+use std::{
+    collections::HashMap,
+    sync::{mpsc::Receiver, Arc, Mutex},
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::{
+    game::Game,
+    game_rules::GameRules,
+    game_stage::GameStage,
+    identity::{ConnectionId, SeatId},
+    nickname::Nickname,
+    random,
+};
+
+pub type RoomId = u32;
+pub type Room = Arc<Mutex<Game>>;
+
+// join codes leave out characters that are easily mistaken for each other
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const CODE_LENGTH: usize = 6;
+
+struct RoomEntry {
+    game: Room,
+    code: Option<String>,
+    password: Option<String>,
+}
+
+// what the lobby shows about a room
+pub struct RoomInfo {
+    pub room_id: RoomId,
+    pub rules: GameRules,
+    pub stage: GameStage,
+    pub nicknames: Vec<Option<Nickname>>,
+    pub spectator_count: usize,
+}
+
+pub struct RoomRegistry {
+    pub max_rooms: usize,
+    rooms: HashMap<RoomId, RoomEntry>,
+    room_count: RoomId,
+}
+
+impl RoomRegistry {
+    pub fn new(max_rooms: usize) -> Self {
+        return RoomRegistry {
+            max_rooms,
+            rooms: HashMap::new(),
+            room_count: 0,
+        };
+    }
+
+    pub fn create_room(&mut self, rules: GameRules) -> Result<(RoomId, Room)> {
+        return self.insert_room(rules, None, None);
+    }
+
+    pub fn create_private_room(
+        &mut self,
+        rules: GameRules,
+        password: Option<String>,
+    ) -> Result<(RoomId, String, Room)> {
+        let code = loop {
+            // the alphabet has 32 letters, so every letter is equally likely
+            let code: String = random::get_secure_bytes::<CODE_LENGTH>()?
+                .iter()
+                .map(|byte| CODE_ALPHABET[*byte as usize % CODE_ALPHABET.len()] as char)
+                .collect();
+
+            if self.find_code(&code).is_none() {
+                break code;
+            }
+        };
+
+        let (room_id, game) = self.insert_room(rules, Some(code.clone()), password)?;
+
+        return Ok((room_id, code, game));
+    }
+
+    // private rooms can only be joined with their code, and password if they have one
+    pub fn get_room(&self, room_id: RoomId) -> Result<Room> {
+        let room = self.rooms.get(&room_id).ok_or(anyhow!("room not found"))?;
+
+        if room.code.is_some() {
+            return Err(anyhow!("cant join room, room is private"));
+        }
+
+        return Ok(room.game.clone());
+    }
+
+    pub fn get_private_room(&self, code: &str, password: Option<&str>) -> Result<(RoomId, Room)> {
+        let room_id = self
+            .find_code(&code.to_uppercase())
+            .ok_or(anyhow!("room not found"))?;
+        let room = &self.rooms[&room_id];
+
+        if room.password.is_some() && room.password.as_deref() != password {
+            return Err(anyhow!("cant join room, wrong password"));
+        }
+
+        return Ok((room_id, room.game.clone()));
+    }
+
+    // a session token gets its holder back into any room, private or not
+    pub fn rejoin_room(
+        &self,
+        room_id: RoomId,
+        connection_id: ConnectionId,
+        token: u64,
+    ) -> Result<(Room, SeatId, Receiver<()>)> {
+        let room = self.rooms.get(&room_id).ok_or(anyhow!("room not found"))?;
+        let (player_id, receiver) = room
+            .game
+            .lock()
+            .map_err(|_| anyhow!("cant rejoin room, room is broken"))?
+            .reconnect(connection_id, token)?;
+
+        return Ok((room.game.clone(), player_id, receiver));
+    }
+
+    // private rooms stay out of the listing, they are only found by their code
+    pub fn get_open_rooms(&self) -> Vec<RoomInfo> {
+        let mut rooms: Vec<RoomInfo> = self
+            .rooms
+            .iter()
+            .filter(|(_, room)| room.code.is_none())
+            .map(|(room_id, room)| {
+                let game = room.game.lock().unwrap();
+                return RoomInfo {
+                    room_id: *room_id,
+                    rules: game.rules,
+                    stage: game.stage,
+                    nicknames: game.get_nicknames(),
+                    spectator_count: game.get_spectator_count(),
+                };
+            })
+            .collect();
+        rooms.sort_by_key(|room| room.room_id);
+
+        return rooms;
+    }
+
+    pub fn get_room_count(&self) -> usize {
+        return self.rooms.len();
+    }
+
+    // a room is done once everyone has left it
+    pub fn cleanup(&mut self) {
+        self.rooms
+            .retain(|_, room| room.game.lock().is_ok_and(|game| !game.is_empty()));
+    }
+
+    fn insert_room(
+        &mut self,
+        rules: GameRules,
+        code: Option<String>,
+        password: Option<String>,
+    ) -> Result<(RoomId, Room)> {
+        rules.validate()?;
+        self.cleanup();
+
+        if self.rooms.len() >= self.max_rooms {
+            return Err(anyhow!("cant create room, server is full"));
+        }
+
+        self.room_count = self.room_count.wrapping_add(1);
+        let game = Arc::new(Mutex::new(Game::new(rules)));
+        self.rooms.insert(
+            self.room_count,
+            RoomEntry {
+                game: game.clone(),
+                code,
+                password,
+            },
+        );
+
+        return Ok((self.room_count, game));
+    }
+
+    fn find_code(&self, code: &str) -> Option<RoomId> {
+        return self
+            .rooms
+            .iter()
+            .find(|(_, room)| room.code.as_deref() == Some(code))
+            .map(|(room_id, _)| *room_id);
+    }
+}
+
+#[cfg(test)]
+mod room_registry_test {
+    use anyhow::Result;
+
+    use super::RoomRegistry;
+    use crate::game_rules::GameRules;
+    use crate::game_stage::GameStage;
+    use crate::identity::ConnectionId;
+    use crate::nickname::Nickname;
+
+    #[test]
+    pub fn test_create_room() -> Result<()> {
+        let mut registry = RoomRegistry::new(2);
+
+        let (room_a, game) = registry.create_room(GameRules::new())?;
+        game.lock().unwrap().connect()?;
+
+        let (room_b, game) = registry.create_room(GameRules::new())?;
+        game.lock().unwrap().connect()?;
+
+        assert_ne!(room_a, room_b);
+        assert!(registry.create_room(GameRules::new()).is_err());
+        assert!(registry.get_room(room_b).is_ok());
+
+        let uneven = GameRules {
+            player_count: 3,
+            team_size: 2,
+            ..GameRules::new()
+        };
+        assert!(RoomRegistry::new(2).create_room(uneven).is_err());
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_create_private_room() -> Result<()> {
+        let mut registry = RoomRegistry::new(2);
+
+        let password = Some(String::from("hunter2"));
+        let (room_id, code, game) = registry.create_private_room(GameRules::new(), password)?;
+        game.lock().unwrap().connect()?;
+
+        assert_eq!(code.len(), 6);
+        assert!(registry.get_room(room_id).is_err());
+        assert!(registry.get_private_room(&code, None).is_err());
+        assert!(registry.get_private_room(&code, Some("hunter3")).is_err());
+
+        let (joined_id, _) = registry.get_private_room(&code.to_lowercase(), Some("hunter2"))?;
+        assert_eq!(joined_id, room_id);
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_get_open_rooms() -> Result<()> {
+        let mut registry = RoomRegistry::new(3);
+
+        let (room_a, game) = registry.create_room(GameRules::new())?;
+        game.lock()
+            .unwrap()
+            .connect_as(ConnectionId::new(), Nickname::try_from("Ahab".as_bytes())?)?;
+        let (room_b, game) = registry.create_room(GameRules::new())?;
+        game.lock().unwrap().connect()?;
+        registry.create_private_room(GameRules::new(), None)?;
+
+        let rooms = registry.get_open_rooms();
+        assert_eq!(rooms.len(), 2);
+        assert_eq!(rooms[0].room_id, room_a);
+        assert_eq!(rooms[0].nicknames[0].as_ref().unwrap().as_str(), "Ahab");
+        assert_eq!(rooms[0].stage, GameStage::Waiting);
+        assert_eq!(rooms[1].room_id, room_b);
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_cleanup() -> Result<()> {
+        let mut registry = RoomRegistry::new(2);
+
+        let (room_id, game) = registry.create_room(GameRules::new())?;
+        let (player_id, _) = game.lock().unwrap().connect()?;
+        game.lock().unwrap().disconnect(player_id)?;
+
+        registry.cleanup();
+
+        assert_eq!(registry.get_room_count(), 0);
+        assert!(registry.get_room(room_id).is_err());
+
+        return Ok(());
+    }
+
+    #[test]
+    pub fn test_cleanup_keeps_rejoinable_rooms() -> Result<()> {
+        let mut registry = RoomRegistry::new(2);
+
+        let rules = GameRules {
+            player_count: 1,
+            team_size: 1,
+            armada_size: 1,
+            ..GameRules::new()
+        };
+        let (room_id, game) = registry.create_room(rules)?;
+        let (player_id, _) = game.lock().unwrap().connect()?;
+        let token = game.lock().unwrap().get_session_token(player_id)?;
+        game.lock().unwrap().disconnect(player_id)?;
+
+        registry.cleanup();
+        assert_eq!(registry.get_room_count(), 1);
+
+        let (_, rejoined_id, _) = registry.rejoin_room(room_id, ConnectionId::new(), token)?;
+        assert_eq!(rejoined_id, player_id);
+
+        // a player who gave up the game doesn't keep the room around
+        game.lock().unwrap().leave(player_id)?;
+        registry.cleanup();
+        assert_eq!(registry.get_room_count(), 0);
+
+        return Ok(());
+    }
+}
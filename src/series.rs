@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+use crate::identity::SeatId;
+
+pub struct Series {
+    pub best_of: u8,
+    wins: HashMap<SeatId, u8>,
+}
+
+impl Series {
+    pub fn new(best_of: u8) -> Self {
+        return Series {
+            best_of,
+            wins: HashMap::new(),
+        };
+    }
+
+    pub fn get_wins(&self, player_id: SeatId) -> u8 {
+        return *self.wins.get(&player_id).unwrap_or(&0);
+    }
+
+    pub fn record_win(&mut self, player_id: SeatId) {
+        *self.wins.entry(player_id).or_insert(0) += 1;
+    }
+
+    pub fn is_won_by(&self, player_id: SeatId) -> bool {
+        return self.get_wins(player_id) > self.best_of / 2;
+    }
+
+    pub fn reset(&mut self) {
+        self.wins.clear();
+    }
+}
+
+#[cfg(test)]
+mod series_test {
+    use crate::identity::SeatId;
+
+    use super::Series;
+
+    #[test]
+    pub fn test_record_win() {
+        let mut series = Series::new(3);
+
+        series.record_win(SeatId(1));
+        assert_eq!(series.get_wins(SeatId(1)), 1);
+        assert_eq!(series.get_wins(SeatId(2)), 0);
+        assert_eq!(series.is_won_by(SeatId(1)), false);
+
+        series.record_win(SeatId(1));
+        assert_eq!(series.is_won_by(SeatId(1)), true);
+    }
+
+    #[test]
+    pub fn test_single_game() {
+        let mut series = Series::new(1);
+
+        series.record_win(SeatId(2));
+        assert_eq!(series.is_won_by(SeatId(2)), true);
+    }
+}
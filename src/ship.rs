@@ -34,7 +34,21 @@ impl TryFrom<u8> for Ship {
 }
 
 impl Ship {
-    pub fn get_points(&self, point: Point) -> Result<Vec<Point>> {
+    pub fn from_size(size: u8, orientation: Orientation) -> Result<Ship> {
+        return match size {
+            SUBMARINE_SIZE => Ok(Ship::Submarine),
+            DESTROYER_SIZE => Ok(Ship::Destroyer(orientation)),
+            CRUISER_SIZE => Ok(Ship::Cruiser(orientation)),
+            BATTLESHIP_SIZE => Ok(Ship::Battleship(orientation)),
+            _ => Err(anyhow!("size does not match any known ship type")),
+        };
+    }
+
+    pub fn get_points(&self, point: Point, board_size: u8) -> Result<Vec<Point>> {
+        if !point.is_on_board(board_size) {
+            return Err(anyhow!("point is out of range"));
+        }
+
         let mut points = Vec::new();
 
         match self {
@@ -43,18 +57,18 @@ impl Ship {
             }
             Self::Destroyer(ort) => {
                 points.push(point);
-                points.push(points[0].get_next(ort)?);
+                points.push(points[0].get_next(ort, board_size)?);
             }
             Self::Cruiser(ort) => {
                 points.push(point);
-                points.push(points[0].get_next(ort)?);
-                points.push(points[1].get_next(ort)?);
+                points.push(points[0].get_next(ort, board_size)?);
+                points.push(points[1].get_next(ort, board_size)?);
             }
             Self::Battleship(ort) => {
                 points.push(point);
-                points.push(points[0].get_next(ort)?);
-                points.push(points[1].get_next(ort)?);
-                points.push(points[2].get_next(ort)?);
+                points.push(points[0].get_next(ort, board_size)?);
+                points.push(points[1].get_next(ort, board_size)?);
+                points.push(points[2].get_next(ort, board_size)?);
             }
         };
 
@@ -66,7 +80,7 @@ impl Ship {
 mod ship_test {
     use anyhow::Result;
 
-    use crate::point::Point;
+    use crate::point::{Point, BOARD_SIZE};
     use crate::ship::Orientation;
 
     use super::Ship;
@@ -76,16 +90,17 @@ mod ship_test {
         let point = Point { x: 0, y: 0 };
 
         let submarine = Ship::Submarine;
-        assert_eq!(submarine.get_points(point)?.len(), 1);
+        assert_eq!(submarine.get_points(point, BOARD_SIZE)?.len(), 1);
 
         let destroyer = Ship::Destroyer(Orientation::Horizontal);
-        assert_eq!(destroyer.get_points(point)?.len(), 2);
+        assert_eq!(destroyer.get_points(point, BOARD_SIZE)?.len(), 2);
 
         let cruiser = Ship::Cruiser(Orientation::Vertical);
-        assert_eq!(cruiser.get_points(point)?.len(), 3);
+        assert_eq!(cruiser.get_points(point, BOARD_SIZE)?.len(), 3);
 
-        let invalid = cruiser.get_points(Point { x: 8, y: 8 });
+        let invalid = cruiser.get_points(Point { x: 8, y: 8 }, BOARD_SIZE);
         assert!(invalid.is_err());
+        assert_eq!(cruiser.get_points(Point { x: 8, y: 8 }, 11)?.len(), 3);
 
         return Ok(());
     }
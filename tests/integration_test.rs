@@ -14,6 +14,7 @@ fn it_plays_start_to_finish() -> Result<()> {
         destroyer_limit: 1,
         cruiser_limit: 0,
         battleship_limit: 0,
+        ..GameRules::new()
     });
 
     assert_eq!(game.stage, GameStage::Waiting);
@@ -59,3 +60,108 @@ fn it_plays_start_to_finish() -> Result<()> {
 
     return Ok(());
 }
+
+#[test]
+fn it_plays_free_for_all() -> Result<()> {
+    let mut game = Game::new(GameRules {
+        player_count: 3,
+        submarine_limit: 1,
+        destroyer_limit: 0,
+        cruiser_limit: 0,
+        battleship_limit: 0,
+        ..GameRules::new()
+    });
+
+    let (player_a, _) = game.connect()?;
+    let (player_b, _) = game.connect()?;
+
+    assert_eq!(game.stage, GameStage::Waiting);
+
+    let (player_c, _) = game.connect()?;
+
+    assert_eq!(game.stage, GameStage::PlayerShips(None));
+
+    game.place_figure(player_a, Ship::Submarine, Point::new(0, 0)?)?;
+    game.place_figure(player_b, Ship::Submarine, Point::new(1, 1)?)?;
+
+    assert_eq!(game.stage, GameStage::PlayerShips(Some(player_c)));
+
+    game.place_figure(player_c, Ship::Submarine, Point::new(2, 2)?)?;
+
+    assert_eq!(game.stage, GameStage::PlayerShoots(player_a));
+    assert!(game.shoot(player_a, Point::new(1, 1)?).is_err());
+
+    game.shoot_at(player_a, 1, Point::new(1, 1)?)?;
+
+    assert_eq!(game.stage, GameStage::PlayerShoots(player_a));
+    assert!(game.shoot_at(player_a, 1, Point::new(1, 2)?).is_err());
+
+    game.shoot(player_a, Point::new(5, 5)?)?;
+
+    assert_eq!(game.stage, GameStage::PlayerShoots(player_c));
+
+    game.shoot(player_c, Point::new(0, 0)?)?;
+
+    assert_eq!(game.stage, GameStage::PlayerWins(player_c));
+
+    let state = game.get_state(player_b)?;
+    assert_eq!(state.enemies.len(), 2);
+    assert_eq!(state.enemies[0].is_alive, false);
+    assert_eq!(state.enemies[1].is_alive, true);
+
+    return Ok(());
+}
+
+#[test]
+fn it_plays_two_on_two() -> Result<()> {
+    let mut game = Game::new(GameRules {
+        player_count: 4,
+        team_size: 2,
+        submarine_limit: 1,
+        destroyer_limit: 0,
+        cruiser_limit: 0,
+        battleship_limit: 0,
+        ..GameRules::new()
+    });
+
+    let (player_a, _) = game.connect()?;
+    let (player_b, _) = game.connect()?;
+    let (player_c, _) = game.connect()?;
+    let (player_d, _) = game.connect()?;
+
+    for player in [player_a, player_b, player_c, player_d] {
+        game.place_figure(player, Ship::Submarine, Point::new(0, 0)?)?;
+    }
+
+    assert_eq!(game.stage, GameStage::PlayerShoots(player_a));
+    assert!(game.shoot(player_a, Point::new(0, 0)?).is_err());
+    assert!(game.shoot_at(player_a, 2, Point::new(0, 0)?).is_err());
+
+    game.shoot_at(player_a, 1, Point::new(0, 0)?)?;
+    game.shoot_at(player_a, 3, Point::new(5, 5)?)?;
+
+    assert_eq!(game.stage, GameStage::PlayerShoots(player_d));
+
+    game.shoot_at(player_d, 0, Point::new(0, 0)?)?;
+
+    assert_eq!(game.stage, GameStage::PlayerShoots(player_d));
+
+    game.shoot(player_d, Point::new(5, 5)?)?;
+
+    assert_eq!(game.stage, GameStage::PlayerShoots(player_c));
+
+    game.shoot(player_c, Point::new(0, 0)?)?;
+
+    assert_eq!(game.stage, GameStage::PlayerWins(player_c));
+
+    let allies = game.get_allies(player_a)?;
+    assert_eq!(allies, vec![player_c]);
+    assert_eq!(game.stage.try_into_u8(player_a, &allies)?, 6);
+
+    let state = game.get_state(player_a)?;
+    assert_eq!(state.allies.len(), 1);
+    assert_eq!(state.allies[0].ships.value, 0b1000 << 124);
+    assert_eq!(state.enemies.len(), 2);
+
+    return Ok(());
+}